@@ -9,7 +9,7 @@ fn main() {
         "Wumpus Hat",
     ];
 
-    let parser = Parser::new(&input, Prefixes::default());
+    let parser = Parser::new(input, Prefixes::default());
     let res = parser.parse();
 
     let ext = parser.match_products(products.clone(), res.clone());
@@ -0,0 +1,236 @@
+//! `kwp`, prints the products of its input that match a keyword list, like grep.
+//!
+//! Exits with `0` when a product was selected, `1` when none was and `2` on errors.
+
+mod output;
+mod record;
+
+use clap::ValueEnum;
+use kwp::{MatchMode, Matcher, ParseErrors, Parser, Prefixes};
+use output::{Output, Writer};
+use record::{Input, Record};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Prints the lines or records that match a keyword list, e.g. `kwp '+jordan,-youth' titles.txt`.
+///
+/// Exits with 0 when a product was selected, 1 when none was and 2 on errors.
+#[derive(Debug, clap::Parser)]
+#[command(name = "kwp", version)]
+struct Cli {
+    /// The keywords, e.g. `+jordan,-youth,price<200`. They may start with `-`.
+    #[arg(allow_hyphen_values = true)]
+    keywords: String,
+    /// Files to read. Standard input is read when none are given, or for `-`.
+    files: Vec<PathBuf>,
+    /// Select the products that don't match.
+    #[arg(short = 'v', long)]
+    invert_match: bool,
+    /// Print how many products were selected instead of the products.
+    #[arg(short, long)]
+    count: bool,
+    /// Match keywords as whole words unless they are written otherwise.
+    #[arg(short, long)]
+    word: bool,
+    /// When to highlight the keywords that hit lines.
+    #[arg(long, value_enum, default_value_t = Color::Auto)]
+    color: Color,
+    /// How to read the input.
+    #[arg(long, value_enum, default_value_t = Input::Lines)]
+    input: Input,
+    /// A field of JSON Lines or CSV records to match keywords against, `title` by default.
+    /// May be given several times.
+    #[arg(long = "field", value_name = "NAME")]
+    fields: Vec<String>,
+    /// How to write the selected products.
+    #[arg(long, value_enum, default_value_t = Output::Records)]
+    output: Output,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Color {
+    /// Only when writing to a terminal.
+    Auto,
+    Always,
+    Never,
+}
+
+fn main() -> ExitCode {
+    let cli = <Cli as clap::Parser>::parse();
+    if !cli.fields.is_empty() && cli.input == Input::Lines {
+        eprintln!("kwp: --field needs --input jsonl or --input csv");
+        return ExitCode::from(2);
+    }
+
+    let mut parser = Parser::new(&cli.keywords, Prefixes::default());
+    if cli.word {
+        parser.set_match_mode(MatchMode::Word);
+    }
+    if cli.input != Input::Lines {
+        // the fields of records are only known once they are read
+        parser.set_fields(None);
+    }
+    let mut matcher = match parser.try_parse() {
+        Ok(keywords) => keywords.compile(),
+        Err(errors) => {
+            report(&cli.keywords, &errors);
+            return ExitCode::from(2);
+        }
+    };
+    if !cli.fields.is_empty() {
+        let fields: Vec<&str> = cli.fields.iter().map(String::as_str).collect();
+        matcher.set_fields(&fields);
+    }
+
+    let stdout = io::stdout();
+    let color = match cli.color {
+        Color::Auto => stdout.is_terminal(),
+        Color::Always => true,
+        Color::Never => false,
+    };
+    let mut out = Writer::new(io::BufWriter::new(stdout.lock()), cli.output);
+    let files = if cli.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        cli.files.clone()
+    };
+    let named = files.len() > 1;
+
+    let mut selected = 0;
+    let mut failed = false;
+    for path in &files {
+        let filter = Filter {
+            matcher: &matcher,
+            cli: &cli,
+            color,
+            name: if named { Some(path.as_path()) } else { None },
+        };
+        let result = open(path).and_then(|reader| {
+            filter.run(reader, &mut out, |error| {
+                eprintln!("kwp: {}: {}", path.display(), error);
+                failed = true;
+            })
+        });
+        match result {
+            Ok(count) => selected += count,
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => return ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("kwp: {}: {}", path.display(), error);
+                failed = true;
+            }
+        }
+    }
+    if let Err(error) = out.finish() {
+        if error.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("kwp: {}", error);
+            failed = true;
+        }
+    }
+
+    if failed {
+        ExitCode::from(2)
+    } else if selected > 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+/// Prints every error in `keywords` with a marker under its span.
+fn report(keywords: &str, errors: &ParseErrors) {
+    eprintln!("kwp: invalid keywords");
+    for error in errors.iter() {
+        let start = keywords[..error.span.start].chars().count();
+        let width = keywords[error.span.clone()].chars().count().max(1);
+        eprintln!("  {}", keywords);
+        eprintln!(
+            "  {}{} {} ({})",
+            " ".repeat(start),
+            "^".repeat(width),
+            error.message,
+            error.code
+        );
+    }
+}
+
+fn open(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    if path == Path::new("-") {
+        Ok(Box::new(BufReader::new(io::stdin())))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// Filters the products of a single input.
+struct Filter<'a> {
+    matcher: &'a Matcher,
+    cli: &'a Cli,
+    color: bool,
+    /// Printed before every line when several inputs are read.
+    name: Option<&'a Path>,
+}
+
+impl Filter<'_> {
+    /// Writes the selected products, or their count, and returns how many were selected.
+    /// Records that can't be read are passed to `skip`, and reading goes on.
+    fn run(
+        &self,
+        reader: Box<dyn BufRead>,
+        out: &mut Writer<impl Write>,
+        mut skip: impl FnMut(io::Error),
+    ) -> io::Result<usize> {
+        let mut selected = 0;
+        for record in record::read(self.cli.input, reader)? {
+            let record = match record {
+                Ok(record) => record,
+                Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                    skip(error);
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            if self.matcher.is_match(&record) == self.cli.invert_match {
+                continue;
+            }
+            selected += 1;
+            if !self.cli.count {
+                out.write(&record, self.name, &self.hits(&record))?;
+            }
+        }
+        if self.cli.count {
+            if let Some(name) = self.name {
+                write!(out.out(), "{}:", name.display())?;
+            }
+            writeln!(out.out(), "{}", selected)?;
+        }
+        Ok(selected)
+    }
+
+    /// The sorted, non-overlapping ranges of the positive keywords that hit a line,
+    /// when they should be highlighted.
+    fn hits(&self, record: &Record) -> Vec<Range<usize>> {
+        if !self.color || self.cli.invert_match || !matches!(record, Record::Line(_)) {
+            return vec![];
+        }
+        let mut ranges: Vec<Range<usize>> = self
+            .matcher
+            .explain(record)
+            .positive
+            .into_iter()
+            .filter_map(|hit| hit.range)
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+
+        let mut merged: Vec<Range<usize>> = vec![];
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
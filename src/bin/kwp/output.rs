@@ -0,0 +1,187 @@
+//! Writing the selected records.
+
+use crate::record::Record;
+use clap::ValueEnum;
+use kwp::Matchable;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// How selected records are written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    /// As they were read, with CSV rows under their header.
+    Records,
+    /// One JSON object per record.
+    Json,
+    /// CSV with a header row.
+    Csv,
+    /// Aligned columns, written once every input has been read.
+    Table,
+}
+
+/// Starts and ends a highlighted hit.
+const HIGHLIGHT: (&str, &str) = ("\x1b[1;31m", "\x1b[0m");
+
+pub struct Writer<W> {
+    out: W,
+    format: Output,
+    /// The columns of CSV and table output, those of the first record written.
+    columns: Option<Vec<String>>,
+    /// The header of the last CSV record written as a record.
+    header: Option<csv::StringRecord>,
+    /// Table rows waiting for the column widths to be known.
+    rows: Vec<Vec<String>>,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(out: W, format: Output) -> Self {
+        Self {
+            out,
+            format,
+            columns: None,
+            header: None,
+            rows: vec![],
+        }
+    }
+
+    /// The underlying output, e.g. to write counts to.
+    pub fn out(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    /// Writes a selected record. When records are written as they were read,
+    /// lines and JSON Lines start with `name`, and `hits` are highlighted in lines.
+    pub fn write(
+        &mut self,
+        record: &Record,
+        name: Option<&Path>,
+        hits: &[Range<usize>],
+    ) -> io::Result<()> {
+        match self.format {
+            Output::Records => self.write_record(record, name, hits),
+            Output::Json => writeln!(self.out, "{}", record.to_json()),
+            Output::Csv => {
+                if self.columns.is_none() {
+                    let columns = record.columns();
+                    writeln!(self.out, "{}", csv_row(&columns))?;
+                    self.columns = Some(columns);
+                }
+                let columns = self.columns.iter().flatten();
+                let row = csv_row(columns.map(|column| cell(record, column)));
+                writeln!(self.out, "{}", row)
+            }
+            Output::Table => {
+                let columns = self.columns.get_or_insert_with(|| record.columns());
+                let row = columns
+                    .iter()
+                    .map(|column| cell(record, column).replace(['\n', '\r', '\t'], " "))
+                    .collect();
+                self.rows.push(row);
+                Ok(())
+            }
+        }
+    }
+
+    fn write_record(
+        &mut self,
+        record: &Record,
+        name: Option<&Path>,
+        hits: &[Range<usize>],
+    ) -> io::Result<()> {
+        if let (Some(name), Record::Line(_) | Record::Json { .. }) = (name, record) {
+            write!(self.out, "{}:", name.display())?;
+        }
+        match record {
+            Record::Line(line) => {
+                let mut end = 0;
+                for range in hits {
+                    write!(
+                        self.out,
+                        "{}{}{}{}",
+                        &line[end..range.start],
+                        HIGHLIGHT.0,
+                        &line[range.clone()],
+                        HIGHLIGHT.1
+                    )?;
+                    end = range.end;
+                }
+                writeln!(self.out, "{}", &line[end..])
+            }
+            Record::Json { raw, .. } => write_line(&mut self.out, raw),
+            Record::Csv { headers, raw, .. } => {
+                if self.header.as_ref() != Some(&headers.names) {
+                    write_line(&mut self.out, &headers.raw)?;
+                    self.header = Some(headers.names.clone());
+                }
+                write_line(&mut self.out, raw)
+            }
+        }
+    }
+
+    /// Writes the table, if any, and flushes the output.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.format == Output::Table {
+            if let Some(columns) = self.columns.take() {
+                let rows = std::mem::take(&mut self.rows);
+                let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+                for row in &rows {
+                    for (width, cell) in widths.iter_mut().zip(row) {
+                        *width = (*width).max(cell.chars().count());
+                    }
+                }
+                self.write_row(&columns, &widths)?;
+                for row in &rows {
+                    self.write_row(row, &widths)?;
+                }
+            }
+        }
+        self.out.flush()
+    }
+
+    fn write_row(&mut self, row: &[String], widths: &[usize]) -> io::Result<()> {
+        let mut line = String::new();
+        for (i, (cell, width)) in row.iter().zip(widths).enumerate() {
+            if i > 0 {
+                line.push_str("  ");
+            }
+            line.push_str(cell);
+            let padding = width - cell.chars().count();
+            line.extend(std::iter::repeat_n(' ', padding));
+        }
+        writeln!(self.out, "{}", line.trim_end())
+    }
+}
+
+/// Writes a line as it was read, adding a line ending when it has none.
+fn write_line(out: &mut impl Write, line: &str) -> io::Result<()> {
+    out.write_all(line.as_bytes())?;
+    if !line.ends_with('\n') {
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// The text of `column` in `record`, empty when it has no such field.
+fn cell(record: &Record, column: &str) -> String {
+    record
+        .field(column)
+        .map(|text| text.into_owned())
+        .unwrap_or_default()
+}
+
+/// Writes a CSV row, quoting the fields that need it.
+fn csv_row<S: AsRef<str>>(fields: impl IntoIterator<Item = S>) -> String {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    fields.join(",")
+}
@@ -0,0 +1,254 @@
+//! Reading the products to filter, as plain lines or as structured records.
+
+use clap::ValueEnum;
+use kwp::Matchable;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::io::{self, BufRead};
+use std::rc::Rc;
+
+/// How the input is read.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Input {
+    /// Every line is a product with a single `title` field.
+    Lines,
+    /// Every non-empty line is a JSON object.
+    Jsonl,
+    /// Comma separated values with a header row naming the fields.
+    Csv,
+}
+
+/// A single product read from the input.
+#[derive(Debug, Clone)]
+pub enum Record {
+    Line(String),
+    Json {
+        /// The line exactly as it was read, with its line ending.
+        raw: String,
+        object: Map<String, Value>,
+    },
+    Csv {
+        headers: Rc<Headers>,
+        values: csv::StringRecord,
+        /// The record exactly as it was read, with its line ending.
+        raw: String,
+    },
+}
+
+/// The header row of a CSV input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Headers {
+    pub names: csv::StringRecord,
+    /// The row exactly as it was read, with its line ending.
+    pub raw: String,
+}
+
+impl Record {
+    /// The names of the fields of the record, in input order for CSV and
+    /// alphabetical order for JSON.
+    pub fn columns(&self) -> Vec<String> {
+        match self {
+            Record::Line(_) => vec!["title".to_string()],
+            Record::Json { object, .. } => object.keys().cloned().collect(),
+            Record::Csv { headers, .. } => headers.names.iter().map(str::to_string).collect(),
+        }
+    }
+
+    /// The record as a JSON object, unchanged when it was read as one.
+    pub fn to_json(&self) -> Cow<'_, str> {
+        match self {
+            Record::Json { raw, .. } => Cow::Borrowed(raw.trim_end_matches(['\n', '\r'])),
+            record => {
+                let object: Map<String, Value> = record
+                    .columns()
+                    .into_iter()
+                    .map(|column| {
+                        let value = record.field(&column).map(Cow::into_owned);
+                        (column, value.map_or(Value::Null, Value::String))
+                    })
+                    .collect();
+                Cow::Owned(Value::Object(object).to_string())
+            }
+        }
+    }
+}
+
+/// Writes JSON values as text: strings as they are, arrays joined with `, `,
+/// so that `tags` can be matched, and anything else as JSON.
+fn json_text(value: &Value) -> Option<Cow<'_, str>> {
+    match value {
+        Value::Null => None,
+        Value::String(text) => Some(Cow::Borrowed(text)),
+        Value::Array(items) => {
+            let items: Vec<Cow<str>> = items.iter().filter_map(json_text).collect();
+            Some(Cow::Owned(items.join(", ")))
+        }
+        value => Some(Cow::Owned(value.to_string())),
+    }
+}
+
+impl Matchable for Record {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        match self {
+            Record::Line(line) => line.field(name),
+            Record::Json { object, .. } => json_text(object.get(name)?),
+            Record::Csv {
+                headers, values, ..
+            } => {
+                let i = headers.names.iter().position(|header| header == name)?;
+                values.get(i).map(Cow::Borrowed)
+            }
+        }
+    }
+}
+
+/// Reads the records of `reader`. Records that can't be read are reported as
+/// [`io::ErrorKind::InvalidData`] errors, after which reading may go on.
+pub fn read(
+    input: Input,
+    reader: Box<dyn BufRead>,
+) -> io::Result<Box<dyn Iterator<Item = io::Result<Record>>>> {
+    Ok(match input {
+        Input::Lines => Box::new(Lines::new(reader).map(|line| {
+            let line = String::from_utf8_lossy(&line?.1)
+                .trim_end_matches('\n')
+                .trim_end_matches('\r')
+                .to_string();
+            Ok(Record::Line(line))
+        })),
+        Input::Jsonl => Box::new(
+            Lines::new(reader)
+                .filter(|line| !matches!(line, Ok((_, bytes)) if bytes.trim_ascii().is_empty()))
+                .map(|line| {
+                    let (number, bytes) = line?;
+                    let raw = String::from_utf8(bytes).map_err(|error| invalid(number, error))?;
+                    match serde_json::from_str(&raw) {
+                        Ok(Value::Object(object)) => Ok(Record::Json { raw, object }),
+                        Ok(_) => Err(invalid(number, "expected a JSON object")),
+                        Err(error) => Err(invalid(number, error)),
+                    }
+                }),
+        ),
+        Input::Csv => {
+            let mut rows = CsvRows::new(reader);
+            let headers = match rows.next().transpose()? {
+                Some((raw, names)) => Rc::new(Headers { names, raw }),
+                None => return Ok(Box::new(std::iter::empty())),
+            };
+            Box::new(rows.map(move |row| {
+                let (raw, values) = row?;
+                Ok(Record::Csv {
+                    headers: Rc::clone(&headers),
+                    values,
+                    raw,
+                })
+            }))
+        }
+    })
+}
+
+fn invalid(line: usize, error: impl ToString) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, error.to_string()),
+    )
+}
+
+/// The lines of a reader with their numbers, as they were read with their line endings.
+struct Lines {
+    reader: Box<dyn BufRead>,
+    number: usize,
+    buffer: Vec<u8>,
+}
+
+impl Lines {
+    fn new(reader: Box<dyn BufRead>) -> Self {
+        Self {
+            reader,
+            number: 0,
+            buffer: vec![],
+        }
+    }
+}
+
+impl Iterator for Lines {
+    type Item = io::Result<(usize, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.clear();
+        match self.reader.read_until(b'\n', &mut self.buffer) {
+            Ok(0) => None,
+            Ok(_) => {
+                self.number += 1;
+                Some(Ok((self.number, self.buffer.clone())))
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// The rows of a CSV input, each as it was read and as parsed. A row spans several lines when a quoted value does.
+/// Empty lines are skipped.
+struct CsvRows {
+    reader: Box<dyn BufRead>,
+    number: usize,
+    buffer: Vec<u8>,
+}
+
+impl CsvRows {
+    fn new(reader: Box<dyn BufRead>) -> Self {
+        Self {
+            reader,
+            number: 0,
+            buffer: vec![],
+        }
+    }
+
+    /// Reads the lines of the next row into the buffer, returning how many there are.
+    /// The quotes of a complete row are balanced, since `""` escapes a quote.
+    fn read_row(&mut self) -> io::Result<usize> {
+        self.buffer.clear();
+        let mut lines = 0;
+        while self.reader.read_until(b'\n', &mut self.buffer)? > 0 {
+            lines += 1;
+            if self.buffer.iter().filter(|b| **b == b'"').count() % 2 == 0 {
+                break;
+            }
+        }
+        Ok(lines)
+    }
+}
+
+impl Iterator for CsvRows {
+    type Item = io::Result<(String, csv::StringRecord)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let lines = match self.read_row() {
+                Ok(0) => return None,
+                Ok(lines) => lines,
+                Err(error) => return Some(Err(error)),
+            };
+            let number = self.number + 1;
+            self.number += lines;
+            if self.buffer.iter().all(|b| *b == b'\r' || *b == b'\n') {
+                continue;
+            }
+
+            let raw = match String::from_utf8(self.buffer.clone()) {
+                Ok(raw) => raw,
+                Err(error) => return Some(Err(invalid(number, error))),
+            };
+            let mut values = csv::StringRecord::new();
+            let parsed = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(raw.as_bytes())
+                .read_record(&mut values);
+            return Some(match parsed {
+                Ok(_) => Ok((raw, values)),
+                Err(error) => Err(invalid(number, error)),
+            });
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! Reads [`Keywords`] from the compact `+foo,-bar` input form in config files.
+//! ## Example
+//! ```
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Task {
+//!     #[serde(deserialize_with = "kwp::compact::deserialize")]
+//!     keywords: kwp::Keywords,
+//! }
+//!
+//! let task: Task = serde_json::from_str(r#"{ "keywords": "+dunk,-youth" }"#).unwrap();
+//! assert_eq!(task.keywords.positive, vec!["dunk"]);
+//! assert_eq!(task.keywords.negative, vec!["youth"]);
+//! ```
+use crate::{Keywords, Parser, Prefixes};
+use serde::{de, Deserialize, Deserializer};
+
+/// Either form [`deserialize`] accepts.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Compact(String),
+    Full(Keywords),
+}
+
+/// Deserializes [`Keywords`] from keyword input, parsed strictly with the default
+/// [`Prefixes`] and parser settings, or from their serialized form. Keywords scoped
+/// to fields other than `title` need the serialized form.
+/// Use it with `#[serde(deserialize_with = "kwp::compact::deserialize")]`.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Keywords, D::Error> {
+    match Repr::deserialize(deserializer)? {
+        Repr::Compact(input) => Parser::new(&input, Prefixes::default())
+            .try_parse()
+            .map_err(de::Error::custom),
+        Repr::Full(keywords) => Ok(keywords),
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+use std::ops::{Deref, Range};
+
+/// Identifies the kind of a [`ParseError`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// A keyword with no text, e.g. the middle of `+foo,,+bar`.
+    EmptyKeyword,
+    /// A prefix with no keyword after it, e.g. `+,`.
+    DanglingPrefix,
+    /// A keyword without a positive or negative prefix.
+    MissingPrefix,
+    /// Whitespace around a keyword or between a prefix and its keyword.
+    StrayWhitespace,
+    /// A `"` that is never closed.
+    UnterminatedQuote,
+    /// A `\` at the very end of the input.
+    TrailingEscape,
+    /// A `(` that is never closed.
+    UnclosedGroup,
+    /// A `)` without a matching `(`.
+    UnmatchedParen,
+    /// Input that cannot appear at its position, e.g. the `(` in `foo(bar)`.
+    UnexpectedToken,
+    /// A `/pattern/flags` keyword that does not compile, or any such keyword
+    /// when the `regex` feature is disabled.
+    InvalidRegex,
+    /// A predicate whose value is not a number, e.g. `price<cheap`.
+    InvalidPredicate,
+}
+
+impl ErrorCode {
+    /// A stable, kebab-case identifier for the code.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::EmptyKeyword => "empty-keyword",
+            ErrorCode::DanglingPrefix => "dangling-prefix",
+            ErrorCode::MissingPrefix => "missing-prefix",
+            ErrorCode::StrayWhitespace => "stray-whitespace",
+            ErrorCode::UnterminatedQuote => "unterminated-quote",
+            ErrorCode::TrailingEscape => "trailing-escape",
+            ErrorCode::UnclosedGroup => "unclosed-group",
+            ErrorCode::UnmatchedParen => "unmatched-paren",
+            ErrorCode::UnexpectedToken => "unexpected-token",
+            ErrorCode::InvalidRegex => "invalid-regex",
+            ErrorCode::InvalidPredicate => "invalid-predicate",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error encountered while parsing keyword input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Byte range of the offending input.
+    pub span: Range<usize>,
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(span: Range<usize>, code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Moves the span `by` bytes to the right.
+    pub(crate) fn offset(mut self, by: usize) -> Self {
+        self.span = self.span.start + by..self.span.end + by;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) at {}..{}",
+            self.message, self.code, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Every error found in the input, in input order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseErrors(pub(crate) Vec<ParseError>);
+
+impl Deref for ParseErrors {
+    type Target = [ParseError];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl IntoIterator for ParseErrors {
+    type Item = ParseError;
+    type IntoIter = std::vec::IntoIter<ParseError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl From<ParseError> for ParseErrors {
+    fn from(error: ParseError) -> Self {
+        Self(vec![error])
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseErrors {}
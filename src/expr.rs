@@ -0,0 +1,201 @@
+use crate::keyword::{Defaults, Haystack};
+use crate::{lexer, ErrorCode, Keyword, ParseError, Polarity, Prefixes};
+
+/// A boolean keyword expression, e.g. `(+jordan|+dunk),+low,-(youth|gs)`.
+///
+/// `,` joins terms that must all match, `|` joins alternatives and parentheses
+/// group them. The negative prefix negates the term or group that follows it,
+/// while the positive prefix is optional. Terms containing any of these
+/// characters can be quoted or escaped as in [`Parser::parse`](crate::Parser::parse).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A keyword, matched case insensitively.
+    Term(Keyword),
+    /// Matches when the inner expression does not.
+    Not(Box<Expr>),
+    /// Matches when every inner expression matches.
+    And(Vec<Expr>),
+    /// Matches when any inner expression matches.
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    /// Evaluates the expression against a product.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let parser = Parser::new("(+jordan|+dunk),+low,-(youth|gs)", Prefixes::default());
+    /// let expr = parser.parse_expr().unwrap();
+    ///
+    /// assert!(expr.matches("Nike Dunk Low Retro"));
+    /// assert!(!expr.matches("Nike Dunk High Retro"));
+    /// assert!(!expr.matches("Jordan 1 Low (GS)"));
+    /// ```
+    pub fn matches(&self, product: &str) -> bool {
+        self.matches_in(&Haystack::new(product))
+    }
+
+    fn matches_in(&self, product: &Haystack) -> bool {
+        match self {
+            Expr::Term(keyword) => keyword.is_match_in(product),
+            Expr::Not(inner) => !inner.matches_in(product),
+            Expr::And(all) => all.iter().all(|e| e.matches_in(product)),
+            Expr::Or(any) => any.iter().any(|e| e.matches_in(product)),
+        }
+    }
+}
+
+/// Recursive descent parser for [`Expr`].
+pub(crate) struct ExprParser<'i, 'p> {
+    input: &'i str,
+    pos: usize,
+    prefixes: Prefixes<'p>,
+    defaults: Defaults<'p>,
+    /// Whether the current term sits under an odd number of negations.
+    negated: bool,
+}
+
+impl<'i, 'p> ExprParser<'i, 'p> {
+    pub(crate) fn new(input: &'i str, prefixes: Prefixes<'p>, defaults: Defaults<'p>) -> Self {
+        Self {
+            input,
+            pos: 0,
+            prefixes,
+            defaults,
+            negated: false,
+        }
+    }
+
+    pub(crate) fn parse(mut self) -> Result<Expr, ParseError> {
+        let expr = self.parse_and()?;
+        self.skip_whitespace();
+        match self.rest().chars().next() {
+            None => Ok(expr),
+            Some(')') => Err(ParseError::new(
+                self.pos..self.pos + 1,
+                ErrorCode::UnmatchedParen,
+                "unmatched `)`",
+            )),
+            Some(c) => Err(ParseError::new(
+                self.pos..self.pos + c.len_utf8(),
+                ErrorCode::UnexpectedToken,
+                format!("unexpected `{}`", c),
+            )),
+        }
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut all = vec![self.parse_or()?];
+        while self.eat(',') {
+            all.push(self.parse_or()?);
+        }
+        Ok(Self::collapse(all, Expr::And))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut any = vec![self.parse_unary()?];
+        while self.eat('|') {
+            any.push(self.parse_unary()?);
+        }
+        Ok(Self::collapse(any, Expr::Or))
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        self.skip_whitespace();
+        match self.prefixes.strip(self.rest()) {
+            Some((Polarity::Negative, prefix)) => {
+                self.eat_prefix(prefix)?;
+                self.negated = !self.negated;
+                let inner = self.parse_primary();
+                self.negated = !self.negated;
+                Ok(Expr::Not(Box::new(inner?)))
+            }
+            Some((_, prefix)) => {
+                self.eat_prefix(prefix)?;
+                self.parse_primary()
+            }
+            None => self.parse_primary(),
+        }
+    }
+
+    fn eat_prefix(&mut self, prefix: &str) -> Result<(), ParseError> {
+        let start = self.pos;
+        self.pos += prefix.len();
+        self.skip_whitespace();
+        if self.rest().is_empty() || lexer::scan(self.rest(), &[',', '|', ')']) == 0 {
+            return Err(ParseError::new(
+                start..start + prefix.len(),
+                ErrorCode::DanglingPrefix,
+                format!("expected a keyword after `{}`", prefix),
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.eat('(') {
+            let inner = self.parse_and()?;
+            if !self.eat(')') {
+                return Err(ParseError::new(
+                    start..start + 1,
+                    ErrorCode::UnclosedGroup,
+                    "unclosed `(`",
+                ));
+            }
+            return Ok(inner);
+        }
+
+        let len = lexer::scan(self.rest(), &[',', '|', '(', ')']);
+        let term = self.rest()[..len].trim_end();
+        self.pos += len;
+        if term.is_empty() {
+            return Err(ParseError::new(
+                start..start,
+                ErrorCode::EmptyKeyword,
+                "expected a keyword",
+            ));
+        }
+        if let Some(error) = lexer::check(term, start) {
+            return Err(error);
+        }
+        let polarity = if self.negated {
+            Polarity::Negative
+        } else {
+            Polarity::Positive
+        };
+        Keyword::parse(term, self.defaults, polarity)
+            .map(Expr::Term)
+            .map_err(|error| error.offset(start))
+    }
+
+    fn collapse(mut exprs: Vec<Expr>, join: fn(Vec<Expr>) -> Expr) -> Expr {
+        if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            join(exprs)
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn rest(&self) -> &'i str {
+        &self.input[self.pos..]
+    }
+}
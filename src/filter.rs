@@ -0,0 +1,84 @@
+use crate::{Matchable, Matcher};
+use std::iter::FusedIterator;
+
+/// An iterator over the products of another iterator that a [`Matcher`] matches,
+/// checking each product only when it is reached.
+/// Created by [`Matcher::filter`] and [`FilterKeywords::filter_keywords`].
+#[derive(Debug, Clone)]
+pub struct Filter<'m, I> {
+    matcher: &'m Matcher,
+    products: I,
+}
+
+impl<'m, I> Filter<'m, I> {
+    pub(crate) fn new(matcher: &'m Matcher, products: I) -> Self {
+        Self { matcher, products }
+    }
+
+    /// Returns the underlying iterator.
+    pub fn into_inner(self) -> I {
+        self.products
+    }
+}
+
+impl<I> Iterator for Filter<'_, I>
+where
+    I: Iterator,
+    I::Item: Matchable,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let matcher = self.matcher;
+        self.products.find(|product| matcher.is_match(product))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.products.size_hint().1)
+    }
+}
+
+impl<I> DoubleEndedIterator for Filter<'_, I>
+where
+    I: DoubleEndedIterator,
+    I::Item: Matchable,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let matcher = self.matcher;
+        self.products.rfind(|product| matcher.is_match(product))
+    }
+}
+
+impl<I> FusedIterator for Filter<'_, I>
+where
+    I: FusedIterator,
+    I::Item: Matchable,
+{
+}
+
+/// Adds [`filter_keywords`](Self::filter_keywords) to every iterator of [`Matchable`] products.
+pub trait FilterKeywords: Iterator + Sized {
+    /// Lazily keeps the products `matcher` matches, see [`Matcher::filter`].
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{FilterKeywords, Parser, Prefixes};
+    /// use std::io::{BufRead, Cursor};
+    ///
+    /// let file = Cursor::new("MyProduct Adult\nMyProduct Youth\nMyProduct Kids\n");
+    /// let matcher = Parser::new("+myproduct,-youth", Prefixes::default()).parse().compile();
+    ///
+    /// let mut lines = file.lines().map_while(Result::ok).filter_keywords(&matcher);
+    /// assert_eq!(lines.next().as_deref(), Some("MyProduct Adult"));
+    /// assert_eq!(lines.next().as_deref(), Some("MyProduct Kids"));
+    /// assert_eq!(lines.next(), None);
+    /// ```
+    fn filter_keywords(self, matcher: &Matcher) -> Filter<'_, Self>
+    where
+        Self::Item: Matchable,
+    {
+        Filter::new(matcher, self)
+    }
+}
+
+impl<I: Iterator> FilterKeywords for I {}
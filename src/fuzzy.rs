@@ -0,0 +1,99 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How many typos a keyword tolerates, counted as edits: inserting, removing
+/// or replacing a character, or swapping two adjacent ones.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fuzziness {
+    /// Only exact matches.
+    #[default]
+    Exact,
+    /// Up to this many edits, whatever the keyword length.
+    Fixed(usize),
+    /// No edits for keywords shorter than `one` characters, one edit for
+    /// keywords shorter than `two` characters and two edits otherwise.
+    Auto { one: usize, two: usize },
+}
+
+impl Fuzziness {
+    /// Exact below 4 characters, one edit below 8 characters and two edits from there on.
+    pub const AUTO: Fuzziness = Fuzziness::Auto { one: 4, two: 8 };
+
+    /// The number of edits allowed for a keyword of `len` characters.
+    /// ## Example
+    /// ```
+    /// use kwp::Fuzziness;
+    ///
+    /// assert_eq!(Fuzziness::AUTO.max_edits(3), 0);
+    /// assert_eq!(Fuzziness::AUTO.max_edits(6), 1);
+    /// assert_eq!(Fuzziness::Fixed(3).max_edits(6), 3);
+    /// ```
+    pub fn max_edits(&self, len: usize) -> usize {
+        match *self {
+            Fuzziness::Exact => 0,
+            Fuzziness::Fixed(edits) => edits,
+            Fuzziness::Auto { one, two } => {
+                if len < one {
+                    0
+                } else if len < two {
+                    1
+                } else {
+                    2
+                }
+            }
+        }
+    }
+}
+
+/// The fewest edits turning `needle` into any substring of `haystack`.
+pub(crate) fn substring_distance(haystack: &str, needle: &str) -> usize {
+    let needle: Vec<char> = needle.chars().collect();
+    let haystack: Vec<char> = haystack.chars().collect();
+    distance(&needle, &haystack, true)
+}
+
+/// The fewest edits turning `needle` into a run of whole words of `haystack`
+/// with as many words as `needle` has.
+pub(crate) fn word_distance(haystack: &str, needle: &str) -> Option<usize> {
+    let needle: Vec<&str> = needle.unicode_words().collect();
+    let words: Vec<&str> = haystack.unicode_words().collect();
+    let expected: Vec<char> = needle.join(" ").chars().collect();
+    words
+        .windows(needle.len().max(1))
+        .map(|window| {
+            let window: Vec<char> = window.join(" ").chars().collect();
+            distance(&expected, &window, false)
+        })
+        .min()
+}
+
+/// Optimal string alignment distance between `a` and `b`, or between `a` and
+/// the closest substring of `b` when `substring` is set.
+fn distance(a: &[char], b: &[char], substring: bool) -> usize {
+    let width = b.len() + 1;
+    let mut rows = vec![vec![0; width]; a.len() + 1];
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = if substring { 0 } else { j };
+    }
+
+    for i in 1..=a.len() {
+        rows[i][0] = i;
+        for j in 1..width {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+
+    let last = &rows[a.len()];
+    if substring {
+        last.iter().copied().min().unwrap_or(0)
+    } else {
+        last[b.len()]
+    }
+}
@@ -0,0 +1,157 @@
+use crate::lexer;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Part {
+    Literal(String),
+    /// `*`, any run of characters.
+    Any,
+    /// `?`, exactly one character.
+    One,
+}
+
+/// A keyword with `*` and `?` wildcards, e.g. `air*1` or `size?`.
+///
+/// `*` matches any run of characters, including none, and `?` matches exactly one.
+/// Wildcards that are quoted or escaped with a backslash are matched literally.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Glob {
+    parts: Vec<Part>,
+}
+
+impl Glob {
+    /// Compiles a glob pattern.
+    /// ## Example
+    /// ```
+    /// use kwp::Glob;
+    ///
+    /// let glob = Glob::new("air*1");
+    /// assert!(glob.is_match("Nike Air Max 1"));
+    /// assert!(!glob.is_match("Nike Air Max 90"));
+    ///
+    /// let glob = Glob::new(r"\*");
+    /// assert!(glob.is_match("*new*"));
+    /// ```
+    pub fn new(pattern: &str) -> Self {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut quoted = false;
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let wildcard = match c {
+                '\\' => {
+                    literal.push(chars.next().unwrap_or('\\'));
+                    continue;
+                }
+                '"' => {
+                    quoted = !quoted;
+                    continue;
+                }
+                '*' if !quoted => Part::Any,
+                '?' if !quoted => Part::One,
+                c => {
+                    literal.push(c);
+                    continue;
+                }
+            };
+            if !literal.is_empty() {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(wildcard);
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Self { parts }
+    }
+
+    /// Checks whether the glob matches anywhere in `text`.
+    /// ⚠️ Case insensitive
+    pub fn is_match(&self, text: &str) -> bool {
+        self.lowercase().find(&text.to_lowercase(), None).is_some()
+    }
+
+    /// Returns a copy with every literal part lowercased.
+    pub(crate) fn lowercase(&self) -> Self {
+        let parts = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(literal) => Part::Literal(literal.to_lowercase()),
+                part => part.clone(),
+            })
+            .collect();
+        Self { parts }
+    }
+
+    /// Finds the leftmost, longest match in `text`. When `bounds` is given,
+    /// the match must start and end on one of those sorted byte offsets.
+    pub(crate) fn find(&self, text: &str, bounds: Option<&[usize]>) -> Option<Range<usize>> {
+        let on_bound = |i: usize| bounds.is_none_or(|b| b.binary_search(&i).is_ok());
+        let starts = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()));
+        for start in starts.filter(|i| on_bound(*i)) {
+            let end = self
+                .ends(text, start)
+                .into_iter()
+                .rev()
+                .find(|i| on_bound(*i));
+            if let Some(end) = end {
+                return Some(start..end);
+            }
+        }
+        None
+    }
+
+    /// Every offset, ascending, at which a match starting at `start` can end.
+    fn ends(&self, text: &str, start: usize) -> Vec<usize> {
+        let mut positions = vec![start];
+        for part in &self.parts {
+            let mut next: Vec<usize> = match part {
+                Part::Literal(literal) => positions
+                    .iter()
+                    .filter(|i| text[**i..].starts_with(literal.as_str()))
+                    .map(|i| i + literal.len())
+                    .collect(),
+                Part::One => positions
+                    .iter()
+                    .filter_map(|i| text[*i..].chars().next().map(|c| i + c.len_utf8()))
+                    .collect(),
+                Part::Any => match positions.first() {
+                    Some(&first) => text[first..]
+                        .char_indices()
+                        .map(|(i, _)| first + i)
+                        .chain(std::iter::once(text.len()))
+                        .collect(),
+                    None => vec![],
+                },
+            };
+            next.sort_unstable();
+            next.dedup();
+            if next.is_empty() {
+                return next;
+            }
+            positions = next;
+        }
+        positions
+    }
+}
+
+/// Writes the glob back as a pattern, quoting literal parts where needed.
+impl fmt::Display for Glob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => f.write_str(&lexer::quote(literal))?,
+                Part::Any => f.write_str("*")?,
+                Part::One => f.write_str("?")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,528 @@
+use crate::{fuzzy, lexer, ErrorCode, Fuzziness, Glob, ParseError, Pattern, Polarity};
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::fmt;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How a keyword is compared against a product.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchMode {
+    /// The keyword may appear anywhere, so `max` matches "Maximum".
+    #[default]
+    Substring,
+    /// The keyword must start and end on a Unicode word boundary,
+    /// so `max` matches "Air Max 90" but not "Maximum".
+    Word,
+}
+
+/// The settings applied to keywords that don't override them with a modifier.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Defaults<'a> {
+    pub(crate) mode: MatchMode,
+    pub(crate) positive_fuzziness: Fuzziness,
+    pub(crate) negative_fuzziness: Fuzziness,
+    /// The fields a `field:` qualifier may name, any field when `None`.
+    pub(crate) fields: Option<&'a [&'a str]>,
+}
+
+/// The fields a `field:` qualifier may name unless the parser is told otherwise.
+pub(crate) const DEFAULT_FIELDS: &[&str] = &["title"];
+
+impl Default for Defaults<'_> {
+    fn default() -> Self {
+        Self {
+            mode: MatchMode::default(),
+            positive_fuzziness: Fuzziness::default(),
+            negative_fuzziness: Fuzziness::default(),
+            fields: Some(DEFAULT_FIELDS),
+        }
+    }
+}
+
+impl Defaults<'_> {
+    pub(crate) fn fuzziness(&self, polarity: Polarity) -> Fuzziness {
+        match polarity {
+            Polarity::Positive => self.positive_fuzziness,
+            Polarity::Negative => self.negative_fuzziness,
+            Polarity::Other => Fuzziness::Exact,
+        }
+    }
+}
+
+/// A single parsed keyword.
+///
+/// A keyword may start with a `field:` qualifier, e.g. `+vendor:nike`, to only be
+/// matched against that [field](crate::Matchable::field) of a product. Only the
+/// fields given to [`Parser::set_fields`](crate::Parser::set_fields) are read as
+/// qualifiers.
+/// It may then be preceded by modifiers: `=` switches it to [`MatchMode::Word`].
+/// It may also be followed by `~` to tolerate typos with [`Fuzziness::AUTO`],
+/// by `~N` to tolerate up to `N` edits, and by `^N` to weigh it `N` times as
+/// much when [scoring](crate::Matcher::score).
+///
+/// Unquoted, unescaped `*` and `?` turn the keyword into a [`Glob`], and with
+/// the `regex` feature a keyword written as `/pattern/flags` becomes a regex.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyword {
+    /// The keyword without its prefix, modifiers, quotes and escapes.
+    pub text: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: MatchMode,
+    /// Ignored for keywords with a [`Pattern`], which never match fuzzily.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fuzziness: Fuzziness,
+    /// When set, the keyword is matched with this pattern instead of its text.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pattern: Option<Pattern>,
+    /// How much a hit counts towards a product's [score](crate::Matcher::score), `1.0` by default.
+    #[cfg_attr(feature = "serde", serde(default = "Keyword::default_weight"))]
+    pub weight: f64,
+    /// The only product field the keyword is matched against. When `None`, it is matched
+    /// against the [default fields](crate::Matcher::set_fields).
+    /// Plain strings only have a `title` field.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub field: Option<String>,
+}
+
+impl Keyword {
+    /// Creates a substring keyword.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            mode: MatchMode::default(),
+            fuzziness: Fuzziness::default(),
+            pattern: None,
+            weight: Self::default_weight(),
+            field: None,
+        }
+    }
+
+    fn default_weight() -> f64 {
+        1.0
+    }
+
+    /// Parses a raw keyword of the given polarity with its prefix already removed.
+    /// Error spans are relative to `raw`.
+    pub(crate) fn parse(
+        raw: &str,
+        defaults: Defaults<'_>,
+        polarity: Polarity,
+    ) -> Result<Self, ParseError> {
+        KeywordRef::parse(raw, defaults, polarity).map(KeywordRef::into_owned)
+    }
+
+    /// Returns a copy with its text lowercased. Regexes are case sensitive and left as they are.
+    pub(crate) fn lowercase(&self) -> Self {
+        let mut keyword = self.clone();
+        match &self.pattern {
+            None => keyword.text = self.text.to_lowercase(),
+            Some(Pattern::Glob(glob)) => {
+                keyword.text = self.text.to_lowercase();
+                keyword.pattern = Some(Pattern::Glob(glob.lowercase()));
+            }
+            #[cfg(feature = "regex")]
+            Some(Pattern::Regex(_)) => {}
+        }
+        keyword
+    }
+
+    /// Splits a leading `field:` qualifier off `body`. Field names are made of ASCII
+    /// letters, digits and underscores, must be one of `fields` when given, and must
+    /// be followed by a keyword. Anything else, like `http:`, stays part of the keyword.
+    fn strip_field<'b>(body: &'b str, fields: Option<&[&str]>) -> Option<(&'b str, &'b str)> {
+        let i = lexer::scan(body, &[':']);
+        let (field, rest) = (&body[..i], body.get(i + 1..)?);
+        let is_name = field.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let is_known = fields.is_none_or(|fields| fields.contains(&field));
+        if is_name && is_known && !rest.is_empty() {
+            Some((field, rest))
+        } else {
+            None
+        }
+    }
+
+    /// Splits a trailing `~` or `~N` off `body`.
+    fn strip_fuzziness(body: &str) -> Option<(&str, Fuzziness)> {
+        let i = lexer::rfind(body, '~')?;
+        let edits = &body[i + 1..];
+        if edits.is_empty() {
+            Some((&body[..i], Fuzziness::AUTO))
+        } else {
+            let edits = edits.parse().ok()?;
+            Some((&body[..i], Fuzziness::Fixed(edits)))
+        }
+    }
+
+    /// Splits a trailing `^N` weight off `body`.
+    fn strip_weight(body: &str) -> Option<(&str, f64)> {
+        let i = lexer::rfind(body, '^')?;
+        let weight: f64 = body[i + 1..].parse().ok()?;
+        if weight.is_finite() && weight >= 0.0 {
+            Some((&body[..i], weight))
+        } else {
+            None
+        }
+    }
+
+    /// Checks whether the keyword appears in `product`.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Keyword, MatchMode};
+    ///
+    /// let keyword = Keyword {
+    ///     mode: MatchMode::Word,
+    ///     ..Keyword::new("max")
+    /// };
+    /// assert!(keyword.is_match("Nike Air Max 90"));
+    /// assert!(!keyword.is_match("Maximum Comfort Tee"));
+    /// ```
+    pub fn is_match(&self, product: &str) -> bool {
+        self.is_match_in(&Haystack::new(product))
+    }
+
+    pub(crate) fn is_match_in(&self, product: &Haystack) -> bool {
+        self.distance_in(product).is_some()
+    }
+
+    /// Returns how many edits away from `product` the keyword is,
+    /// or `None` when that is more than its [`Fuzziness`] allows.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Fuzziness, Keyword};
+    ///
+    /// let keyword = Keyword {
+    ///     fuzziness: Fuzziness::AUTO,
+    ///     ..Keyword::new("hoodie")
+    /// };
+    /// assert_eq!(keyword.distance("Blurple Hoodie"), Some(0));
+    /// assert_eq!(keyword.distance("Blurple Hooide"), Some(1));
+    /// assert_eq!(keyword.distance("Blurple Tee"), None);
+    /// ```
+    pub fn distance(&self, product: &str) -> Option<usize> {
+        self.distance_in(&Haystack::new(product))
+    }
+
+    pub(crate) fn distance_in(&self, product: &Haystack) -> Option<usize> {
+        if self.field.as_deref().is_some_and(|field| field != "title") {
+            return None;
+        }
+        if let Some(pattern) = &self.pattern {
+            return pattern.lowercase().find(product, self.mode).map(|_| 0);
+        }
+
+        let product = product.lower.as_str();
+        let text = self.text.to_lowercase();
+        let exact = match self.mode {
+            MatchMode::Substring => product.contains(&text),
+            MatchMode::Word => contains_word(product, &text),
+        };
+        if exact {
+            return Some(0);
+        }
+        self.fuzzy_distance(product, &text)
+    }
+
+    /// The distance between the lowercased `product` and `text`, the lowercased
+    /// keyword, when the keyword's fuzziness allows it.
+    pub(crate) fn fuzzy_distance(&self, product: &str, text: &str) -> Option<usize> {
+        let max_edits = self.fuzziness.max_edits(text.chars().count());
+        if max_edits == 0 {
+            return None;
+        }
+        let edits = match self.mode {
+            MatchMode::Substring => Some(fuzzy::substring_distance(product, text)),
+            MatchMode::Word => fuzzy::word_distance(product, text),
+        };
+        edits.filter(|edits| *edits <= max_edits)
+    }
+}
+
+/// Writes the keyword as it is written after its prefix, so that parsing it with the
+/// default [`Options`](crate::Options) gives it back. [`Fuzziness::Auto`] thresholds
+/// other than [`Fuzziness::AUTO`] cannot be written and are written as `~`.
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(field) = &self.field {
+            write!(f, "{}:", field)?;
+        }
+        if self.mode == MatchMode::Word {
+            f.write_str("=")?;
+        }
+        match &self.pattern {
+            Some(Pattern::Glob(glob)) => write!(f, "{}", glob)?,
+            #[cfg(feature = "regex")]
+            Some(Pattern::Regex(regex)) => write!(f, "/{}/", lexer::escape_regex(regex.as_str()))?,
+            None => f.write_str(&lexer::quote(&self.text))?,
+        }
+        match self.fuzziness {
+            Fuzziness::Exact => {}
+            Fuzziness::Fixed(edits) => write!(f, "~{}", edits)?,
+            Fuzziness::Auto { .. } => f.write_str("~")?,
+        }
+        if self.weight != 1.0 {
+            write!(f, "^{}", self.weight)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for Keyword {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for Keyword {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+/// A [`Keyword`] borrowing its text and field from the parsed input, see
+/// [`Parser::parse_borrowed`](crate::Parser::parse_borrowed).
+///
+/// The text is only allocated when quotes or escapes had to be removed from it,
+/// or when it is a regex with flags.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct KeywordRef<'a> {
+    /// The keyword without its prefix, modifiers, quotes and escapes.
+    pub text: Cow<'a, str>,
+    pub mode: MatchMode,
+    /// Ignored for keywords with a [`Pattern`], which never match fuzzily.
+    pub fuzziness: Fuzziness,
+    /// When set, the keyword is matched with this pattern instead of its text.
+    pub pattern: Option<Pattern>,
+    /// How much a hit counts towards a product's [score](crate::Matcher::score), `1.0` by default.
+    pub weight: f64,
+    /// The only product field the keyword is matched against, see [`Keyword::field`].
+    pub field: Option<&'a str>,
+}
+
+impl<'a> KeywordRef<'a> {
+    /// Creates a substring keyword.
+    pub fn new(text: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            text: text.into(),
+            mode: MatchMode::default(),
+            fuzziness: Fuzziness::default(),
+            pattern: None,
+            weight: Keyword::default_weight(),
+            field: None,
+        }
+    }
+
+    /// Parses a raw keyword of the given polarity with its prefix already removed.
+    /// Error spans are relative to `raw`.
+    pub(crate) fn parse(
+        raw: &'a str,
+        defaults: Defaults<'_>,
+        polarity: Polarity,
+    ) -> Result<Self, ParseError> {
+        let mut keyword = Self::new("");
+        keyword.mode = defaults.mode;
+        keyword.fuzziness = defaults.fuzziness(polarity);
+
+        let mut body = raw;
+        if let Some((field, rest)) = Keyword::strip_field(body, defaults.fields) {
+            keyword.field = Some(field);
+            body = rest;
+        }
+        if let Some(rest) = body.strip_prefix('=') {
+            keyword.mode = MatchMode::Word;
+            body = rest;
+        }
+        if keyword.field.is_none() {
+            if let Some((field, rest)) = Keyword::strip_field(body, defaults.fields) {
+                keyword.field = Some(field);
+                body = rest;
+            }
+        }
+        let offset = raw.len() - body.len();
+        loop {
+            if let Some((rest, fuzziness)) = Keyword::strip_fuzziness(body) {
+                keyword.fuzziness = fuzziness;
+                body = rest;
+            } else if let Some((rest, weight)) = Keyword::strip_weight(body) {
+                keyword.weight = weight;
+                body = rest;
+            } else {
+                break;
+            }
+        }
+        if let Some((source, regex)) = Pattern::parse_regex(body) {
+            let regex = regex.map_err(|message| {
+                ParseError::new(
+                    offset..offset + body.len(),
+                    ErrorCode::InvalidRegex,
+                    message,
+                )
+            })?;
+            keyword.text = source;
+            keyword.pattern = Some(regex);
+            return Ok(keyword);
+        }
+        if lexer::scan(body, &['*', '?']) < body.len() {
+            keyword.pattern = Some(Pattern::Glob(Glob::new(body)));
+        }
+        keyword.text = lexer::unescape(body);
+        Ok(keyword)
+    }
+
+    /// Like [`parse`](Self::parse), but falls back to the unescaped text when the keyword is invalid.
+    pub(crate) fn parse_lenient(raw: &'a str, defaults: Defaults<'_>, polarity: Polarity) -> Self {
+        Self::parse(raw, defaults, polarity).unwrap_or_else(|_| Self::new(lexer::unescape(raw)))
+    }
+
+    /// Copies the borrowed text and field, e.g. to keep the keyword past the input's lifetime.
+    /// ## Example
+    /// ```
+    /// use kwp::{Keyword, Parser, Prefixes};
+    ///
+    /// let mut parser = Parser::new("+vendor:nike", Prefixes::default());
+    /// parser.set_fields(Some(&["vendor"]));
+    /// let keyword: Keyword = parser.parse_borrowed().positive[0].clone().into_owned();
+    /// assert_eq!(keyword.field.as_deref(), Some("vendor"));
+    /// ```
+    pub fn into_owned(self) -> Keyword {
+        Keyword {
+            text: self.text.into_owned(),
+            mode: self.mode,
+            fuzziness: self.fuzziness,
+            pattern: self.pattern,
+            weight: self.weight,
+            field: self.field.map(str::to_string),
+        }
+    }
+}
+
+impl From<KeywordRef<'_>> for Keyword {
+    fn from(keyword: KeywordRef<'_>) -> Self {
+        keyword.into_owned()
+    }
+}
+
+/// Compares the keyword text only.
+impl PartialEq<str> for KeywordRef<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.text == other
+    }
+}
+
+/// Compares the keyword text only.
+impl<'a> PartialEq<&'a str> for KeywordRef<'_> {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.text == *other
+    }
+}
+
+/// Compares the keyword text only.
+impl PartialEq<str> for Keyword {
+    fn eq(&self, other: &str) -> bool {
+        self.text == other
+    }
+}
+
+/// Compares the keyword text only.
+impl<'a> PartialEq<&'a str> for Keyword {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.text == *other
+    }
+}
+
+/// A product prepared for matching.
+pub(crate) struct Haystack<'a> {
+    pub(crate) text: Cow<'a, str>,
+    pub(crate) lower: String,
+    /// `(lower offset, text offset)` of every char and of the end, built on first use.
+    offsets: OnceCell<Vec<(usize, usize)>>,
+    /// The [`word_bounds`] of `lower`, built on first use.
+    bounds: OnceCell<Vec<usize>>,
+    /// The [`word_bounds`] of `text`, built on first use.
+    text_bounds: OnceCell<Vec<usize>>,
+}
+
+impl<'a> Haystack<'a> {
+    pub(crate) fn new(text: impl Into<Cow<'a, str>>) -> Self {
+        let text = text.into();
+        Self {
+            lower: text.to_lowercase(),
+            text,
+            offsets: OnceCell::new(),
+            bounds: OnceCell::new(),
+            text_bounds: OnceCell::new(),
+        }
+    }
+
+    /// The [`word_bounds`] of `lower`.
+    pub(crate) fn bounds(&self) -> &[usize] {
+        self.bounds.get_or_init(|| word_bounds(&self.lower))
+    }
+
+    /// The [`word_bounds`] of `text`.
+    #[cfg_attr(not(feature = "regex"), allow(dead_code))]
+    pub(crate) fn text_bounds(&self) -> &[usize] {
+        self.text_bounds.get_or_init(|| word_bounds(&self.text))
+    }
+
+    /// Maps a byte range in `lower` back to the chars it covers in `text`.
+    pub(crate) fn to_text(&self, range: Range<usize>) -> Range<usize> {
+        let offsets = self.offsets();
+        let start = offsets.partition_point(|(lower, _)| *lower <= range.start) - 1;
+        let end = offsets.partition_point(|(lower, _)| *lower < range.end);
+        offsets[start].1..offsets[end.max(start)].1
+    }
+
+    /// Maps a byte range in `text` to the same chars in `lower`.
+    #[cfg_attr(not(feature = "regex"), allow(dead_code))]
+    pub(crate) fn to_lower(&self, range: Range<usize>) -> Range<usize> {
+        let offsets = self.offsets();
+        let lower = |i: usize| offsets[offsets.partition_point(|(_, text)| *text < i)].0;
+        lower(range.start)..lower(range.end)
+    }
+
+    fn offsets(&self) -> &[(usize, usize)] {
+        self.offsets.get_or_init(|| {
+            let mut offsets = Vec::with_capacity(self.text.len() + 1);
+            let mut lower = 0;
+            for (i, c) in self.text.char_indices() {
+                offsets.push((lower, i));
+                lower += c.to_lowercase().map(char::len_utf8).sum::<usize>();
+            }
+            offsets.push((lower, self.text.len()));
+            offsets
+        })
+    }
+}
+
+/// Checks whether `needle` occurs in `haystack` starting and ending on word boundaries.
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    let bounds = word_bounds(haystack);
+    let is_bound = |i: usize| bounds.binary_search(&i).is_ok();
+
+    let mut from = 0;
+    while let Some(found) = haystack[from..].find(needle) {
+        let start = from + found;
+        if is_bound(start) && is_bound(start + needle.len()) {
+            return true;
+        }
+        match haystack[start..].chars().next() {
+            Some(c) => from = start + c.len_utf8(),
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Every byte offset of `text` that is a Unicode word boundary, ascending.
+pub(crate) fn word_bounds(text: &str) -> Vec<usize> {
+    let mut bounds: Vec<usize> = text.split_word_bound_indices().map(|(i, _)| i).collect();
+    bounds.push(text.len());
+    bounds
+}
@@ -0,0 +1,167 @@
+//! Quote and escape aware scanning shared by the keyword and expression parsers.
+//!
+//! A keyword may be wrapped in double quotes (`+"air max 1, 86"`) and any
+//! character may be escaped with a backslash (`\,`, `\"`, `\\`, `\+`).
+
+use crate::{ErrorCode, ParseError};
+use std::borrow::Cow;
+
+/// Characters that mean something inside a keyword or an expression.
+const SPECIAL: [char; 11] = [',', '"', '\\', '*', '?', '~', '^', ':', '(', ')', '|'];
+
+/// Returns the byte length of the leading part of `input` that has none of
+/// `stops` outside of quotes and escapes.
+pub(crate) fn scan(input: &str, stops: &[char]) -> usize {
+    let mut quoted = false;
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if !quoted && stops.contains(&c) => return i,
+            _ => {}
+        }
+    }
+    input.len()
+}
+
+/// Returns the byte offset of the last `c` in `input` that is neither quoted nor escaped.
+pub(crate) fn rfind(input: &str, c: char) -> Option<usize> {
+    let mut found = None;
+    let mut offset = 0;
+    while offset < input.len() {
+        let len = scan(&input[offset..], &[c]);
+        if offset + len == input.len() {
+            break;
+        }
+        found = Some(offset + len);
+        offset += len + c.len_utf8();
+    }
+    found
+}
+
+/// Reports unterminated quotes and trailing escapes in a raw keyword found at `offset`.
+pub(crate) fn check(raw: &str, offset: usize) -> Option<ParseError> {
+    let mut quote = None;
+    let mut chars = raw.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if chars.next().is_none() => {
+                return Some(ParseError::new(
+                    offset + i..offset + raw.len(),
+                    ErrorCode::TrailingEscape,
+                    "nothing to escape after `\\`",
+                ));
+            }
+            '\\' => {}
+            '"' => quote = if quote.is_some() { None } else { Some(i) },
+            _ => {}
+        }
+    }
+    quote.map(|i| {
+        ParseError::new(
+            offset + i..offset + raw.len(),
+            ErrorCode::UnterminatedQuote,
+            "unterminated quote",
+        )
+    })
+}
+
+/// Removes quotes and backslash escapes from a raw keyword, borrowing it when it has none.
+pub(crate) fn unescape(raw: &str) -> Cow<'_, str> {
+    if !raw.contains(['"', '\\']) {
+        return Cow::Borrowed(raw);
+    }
+    let mut text = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.push(chars.next().unwrap_or('\\')),
+            '"' => {}
+            c => text.push(c),
+        }
+    }
+    Cow::Owned(text)
+}
+
+/// Characters a regex keyword must escape to keep them from being read as syntax.
+pub(crate) const REGEX_ESCAPED: [char; 2] = [',', '"'];
+
+/// Removes the backslashes before [`REGEX_ESCAPED`] characters in the source of a
+/// regex keyword, leaving every other escape for the regex itself.
+pub(crate) fn unescape_regex(source: &str) -> Cow<'_, str> {
+    if !source.contains('\\') {
+        return Cow::Borrowed(source);
+    }
+    let mut text = String::with_capacity(source.len());
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        text.push(c);
+        if c == '\\' {
+            match chars.next() {
+                Some(escaped) if REGEX_ESCAPED.contains(&escaped) => {
+                    text.pop();
+                    text.push(escaped);
+                }
+                escaped => text.extend(escaped),
+            }
+        }
+    }
+    Cow::Owned(text)
+}
+
+/// Escapes the [`REGEX_ESCAPED`] characters in a regex source, so that
+/// [`unescape_regex`] gives it back. A source that already escapes one of them,
+/// as in `a\,b`, is given back without the redundant backslash.
+#[cfg_attr(not(feature = "regex"), allow(dead_code))]
+pub(crate) fn escape_regex(source: &str) -> Cow<'_, str> {
+    if !source.contains(REGEX_ESCAPED) {
+        return Cow::Borrowed(source);
+    }
+    let mut escaped = String::with_capacity(source.len() + 2);
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                escaped.push(c);
+                escaped.extend(chars.next());
+            }
+            c if REGEX_ESCAPED.contains(&c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Writes `text` so that [`unescape`] gives it back and none of it is read as
+/// syntax, quoting it only when needed.
+pub(crate) fn quote(text: &str) -> Cow<'_, str> {
+    let plain = !text.is_empty()
+        && !text.contains(SPECIAL)
+        && !text.starts_with(['=', '/'])
+        && text.trim() == text;
+    if plain {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(force_quote(text))
+    }
+}
+
+/// Wraps `text` in quotes, escaping the quotes and backslashes in it.
+pub(crate) fn force_quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
@@ -18,7 +18,47 @@
 //! ```
 // to test this, cargo test -- +foo,-bar,+baz
 
-use std::str::Split;
+#[cfg(feature = "serde")]
+pub mod compact;
+mod error;
+mod expr;
+mod filter;
+mod fuzzy;
+mod glob;
+mod keyword;
+mod lexer;
+mod matchable;
+mod matcher;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pattern;
+mod predicate;
+#[cfg(feature = "shopify")]
+pub mod shopify;
+mod size;
+#[cfg(feature = "futures")]
+mod stream;
+mod token;
+
+pub use error::{ErrorCode, ParseError, ParseErrors};
+pub use expr::Expr;
+pub use filter::{Filter, FilterKeywords};
+pub use fuzzy::Fuzziness;
+pub use glob::Glob;
+pub use keyword::{Keyword, KeywordRef, MatchMode};
+pub use matchable::{Listing, Matchable, Variants, WithVariants};
+pub use matcher::{Explanation, KeywordHit, Matcher, VariantMatch};
+pub use pattern::Pattern;
+pub use predicate::{Comparison, Predicate};
+pub use size::{Size, SizeFilter, SizeRange, SizeSystem};
+#[cfg(feature = "futures")]
+pub use stream::{FilterKeywordsStream, FilterStream};
+pub use token::{Polarity, Token, TokenKind, Tokens};
+
+use expr::ExprParser;
+use keyword::Defaults;
+use std::borrow::Cow;
+use std::fmt;
 
 /// Shorthand for parsed data from the parse function.
 pub type Parsed = Vec<String>;
@@ -26,19 +66,218 @@ pub type Parsed = Vec<String>;
 pub type ParsedResponse = (Parsed, Parsed, Parsed);
 
 /// Represents the positive and negative keyword prefixes for parsing.
+/// Deserializing them borrows from the input, see [`PrefixesBuf`] for owned prefixes.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Prefixes<'a> {
     pub positive: &'a str,
     pub negative: &'a str,
 }
 
-#[derive(Debug, Clone)]
+/// Owned [`Prefixes`], e.g. to keep them in a config that is read with
+/// `serde_json::from_reader` or sent between services.
+/// ## Example
+/// ```
+/// use kwp::{Parser, PrefixesBuf};
+///
+/// let prefixes = PrefixesBuf {
+///     positive: "++".to_string(),
+///     negative: "--".to_string(),
+/// };
+/// let keywords = Parser::new("++foo,--bar", prefixes.as_prefixes()).parse();
+/// assert_eq!(keywords.positive, vec!["foo"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrefixesBuf {
+    pub positive: String,
+    pub negative: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Keywords {
-    pub positive: Parsed,
-    pub negative: Parsed,
+    pub positive: Vec<Keyword>,
+    pub negative: Vec<Keyword>,
+    /// Unprefixed comparisons like `price<200`, which every matching product must satisfy.
+    pub predicates: Vec<Predicate>,
+    /// Unprefixed size predicates like `size:9-11.5`, which every matching product must satisfy.
+    pub sizes: Vec<SizeFilter>,
     pub other: Parsed,
 }
 
+/// [`Keywords`] borrowing their text from the parsed input, returned by
+/// [`Parser::parse_borrowed`]. Use [`into_owned`](Self::into_owned) to keep them
+/// past the input's lifetime.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct KeywordsRef<'a> {
+    pub positive: Vec<KeywordRef<'a>>,
+    pub negative: Vec<KeywordRef<'a>>,
+    /// See [`Keywords::predicates`].
+    pub predicates: Vec<Predicate>,
+    /// See [`Keywords::sizes`].
+    pub sizes: Vec<SizeFilter>,
+    pub other: Vec<Cow<'a, str>>,
+}
+
+impl<'a> Prefixes<'a> {
+    /// Copies the prefixes into [`PrefixesBuf`].
+    pub fn into_owned(self) -> PrefixesBuf {
+        PrefixesBuf {
+            positive: self.positive.to_string(),
+            negative: self.negative.to_string(),
+        }
+    }
+
+    /// Finds the prefix `token` starts with, preferring the longer one when both match.
+    /// An empty prefix never matches.
+    pub(crate) fn strip(&self, token: &str) -> Option<(Polarity, &'a str)> {
+        let positive = (Polarity::Positive, self.positive);
+        let negative = (Polarity::Negative, self.negative);
+        [positive, negative]
+            .iter()
+            .filter(|(_, prefix)| !prefix.is_empty() && token.starts_with(prefix))
+            .max_by_key(|(_, prefix)| prefix.len())
+            .copied()
+    }
+}
+
+impl Keywords {
+    /// Compiles the keywords into a [`Matcher`] for scanning many products quickly.
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let products = vec!["Youth Tee", "Blurple Hoodie", "Wumpus Hat"];
+    /// let matcher = Parser::new("-youth,+hoodie,+hat", Prefixes::default())
+    ///     .parse()
+    ///     .compile();
+    ///
+    /// assert_eq!(matcher.match_products(&products), vec!["Blurple Hoodie", "Wumpus Hat"]);
+    /// ```
+    pub fn compile(&self) -> Matcher {
+        Matcher::new(self)
+    }
+
+    /// Writes the keywords back as input for the given prefixes, so that parsing it
+    /// with the default [`Options`] gives them back. Text is quoted only where needed.
+    /// Keywords come first, then `other`, predicates and size predicates.
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let prefixes = Prefixes { positive: "++", negative: "--" };
+    /// let keywords = Parser::new(r#"++"air max 1, 86",--youth~,++=dunk^2"#, prefixes).parse();
+    ///
+    /// assert_eq!(keywords.format(prefixes), r#"++"air max 1, 86",++=dunk^2,--youth~"#);
+    /// ```
+    pub fn format(&self, prefixes: Prefixes) -> String {
+        let positive = self
+            .positive
+            .iter()
+            .map(|keyword| format!("{}{}", prefixes.positive, keyword));
+        let negative = self
+            .negative
+            .iter()
+            .map(|keyword| format!("{}{}", prefixes.negative, keyword));
+        let other = self.other.iter().map(|text| {
+            let trimmed = text.trim();
+            let ambiguous = prefixes.strip(text).is_some()
+                || Predicate::parse(trimmed).is_some()
+                || SizeFilter::parse(trimmed).is_some();
+            if ambiguous {
+                lexer::force_quote(text)
+            } else {
+                lexer::quote(text).into_owned()
+            }
+        });
+        let predicates = self.predicates.iter().map(ToString::to_string);
+        let sizes = self.sizes.iter().map(ToString::to_string);
+
+        let tokens: Vec<String> = positive
+            .chain(negative)
+            .chain(other)
+            .chain(predicates)
+            .chain(sizes)
+            .collect();
+        tokens.join(",")
+    }
+
+    /// Returns the canonical form of the keywords: every keyword lowercased, except
+    /// for case sensitive regexes, then each list sorted by how it is written and
+    /// deduplicated. Equivalent keyword sets have the same canonical form.
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let a = Parser::new("+Jordan,-youth,+dunk,+jordan", Prefixes::default()).parse();
+    /// let b = Parser::new("-Youth,+DUNK,+jordan", Prefixes::default()).parse();
+    ///
+    /// assert_eq!(a.canonical(), b.canonical());
+    /// assert_eq!(a.canonical().to_string(), "+dunk,+jordan,-youth");
+    /// ```
+    pub fn canonical(&self) -> Keywords {
+        fn normalize<T: ToString + PartialEq>(mut items: Vec<T>) -> Vec<T> {
+            items.sort_by_cached_key(ToString::to_string);
+            items.dedup();
+            items
+        }
+
+        Keywords {
+            positive: normalize(self.positive.iter().map(Keyword::lowercase).collect()),
+            negative: normalize(self.negative.iter().map(Keyword::lowercase).collect()),
+            predicates: normalize(self.predicates.clone()),
+            sizes: normalize(self.sizes.clone()),
+            other: normalize(self.other.iter().map(|text| text.to_lowercase()).collect()),
+        }
+    }
+}
+
+impl KeywordsRef<'_> {
+    /// Copies everything borrowed from the input.
+    /// ## Example
+    /// ```
+    /// use kwp::{Keywords, Parser, Prefixes};
+    ///
+    /// fn parse(input: &str) -> Keywords {
+    ///     Parser::new(input, Prefixes::default()).parse_borrowed().into_owned()
+    /// }
+    ///
+    /// let keywords = parse(&String::from("+foo,-bar"));
+    /// assert_eq!(keywords.positive, vec!["foo"]);
+    /// assert_eq!(keywords.negative, vec!["bar"]);
+    /// ```
+    pub fn into_owned(self) -> Keywords {
+        Keywords {
+            positive: self.positive.into_iter().map(Keyword::from).collect(),
+            negative: self.negative.into_iter().map(Keyword::from).collect(),
+            predicates: self.predicates,
+            sizes: self.sizes,
+            other: self.other.into_iter().map(Cow::into_owned).collect(),
+        }
+    }
+
+    /// Compiles the keywords into a [`Matcher`], see [`Keywords::compile`].
+    pub fn compile(&self) -> Matcher {
+        self.clone().into_owned().compile()
+    }
+}
+
+impl From<KeywordsRef<'_>> for Keywords {
+    fn from(keywords: KeywordsRef<'_>) -> Self {
+        keywords.into_owned()
+    }
+}
+
+/// Writes the keywords back as input for the default [`Prefixes`], see [`Keywords::format`].
+impl fmt::Display for Keywords {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(Prefixes::default()))
+    }
+}
+
 /// Default options for the Prefixes structure.
 impl<'a> Default for Prefixes<'a> {
     fn default() -> Self {
@@ -49,11 +288,51 @@ impl<'a> Default for Prefixes<'a> {
     }
 }
 
+impl PrefixesBuf {
+    /// Borrows the prefixes, e.g. to create a [`Parser`].
+    pub fn as_prefixes(&self) -> Prefixes<'_> {
+        Prefixes {
+            positive: &self.positive,
+            negative: &self.negative,
+        }
+    }
+}
+
+/// The default [`Prefixes`], `+` and `-`.
+impl Default for PrefixesBuf {
+    fn default() -> Self {
+        Prefixes::default().into_owned()
+    }
+}
+
+impl From<Prefixes<'_>> for PrefixesBuf {
+    fn from(prefixes: Prefixes<'_>) -> Self {
+        prefixes.into_owned()
+    }
+}
+
+/// Parser settings, everything but the input and the prefixes.
+/// Read with [`Parser::options`] and applied with [`Parser::set_options`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Options {
+    /// See [`Parser::should_retain_prefix`].
+    pub retain_prefix: bool,
+    /// See [`Parser::set_match_mode`].
+    pub match_mode: MatchMode,
+    /// See [`Parser::set_fuzziness`].
+    pub positive_fuzziness: Fuzziness,
+    /// See [`Parser::set_fuzziness`].
+    pub negative_fuzziness: Fuzziness,
+}
+
 /// Represents the main parser
 pub struct Parser<'a> {
-    input: String,
+    input: &'a str,
     pub prefixes: Prefixes<'a>,
     retain_prefix: bool,
+    defaults: Defaults<'a>,
 }
 
 impl<'a> Parser<'a> {
@@ -64,11 +343,12 @@ impl<'a> Parser<'a> {
     ///
     /// let parser = Parser::new("+foo,-bar", Prefixes::default());
     /// ```
-    pub fn new(input: &str, prefixes: Prefixes<'a>) -> Self {
+    pub fn new(input: &'a str, prefixes: Prefixes<'a>) -> Self {
         Self {
-            input: input.to_string(),
+            input,
             prefixes,
             retain_prefix: false,
+            defaults: Defaults::default(),
         }
     }
 
@@ -91,47 +371,326 @@ impl<'a> Parser<'a> {
         bool
     }
 
-    /// Parses the provided split with the prefix
-    fn parse_with_prefix(&self, split: Split<&str>, prefix: &str) -> Vec<String> {
-        return split
-            .filter(|e| e.starts_with(&prefix))
-            .map(|e| {
-                if !self.retain_prefix {
-                    e.replace(&prefix, "")
-                } else {
-                    e.to_string()
-                }
-            })
-            .collect();
+    /// Sets how keywords without a mode modifier are matched, [`MatchMode::Substring`] by default.
+    /// Prefixing a single keyword with `=` always matches it as a whole word.
+    ///
+    /// ## Example
+    /// ```
+    /// use kwp::{MatchMode, Parser, Prefixes};
+    ///
+    /// let mut parser = Parser::new("+max,-kid", Prefixes::default());
+    /// parser.set_match_mode(MatchMode::Word);
+    ///
+    /// let keywords = parser.parse();
+    /// let products = vec!["Air Max Kidney Bean Tee", "Maximum Tee"];
+    /// let products = parser.match_products(products, keywords);
+    /// assert_eq!(products, vec!["Air Max Kidney Bean Tee"]);
+    /// ```
+    pub fn set_match_mode(&mut self, mode: MatchMode) -> MatchMode {
+        self.defaults.mode = mode;
+        mode
+    }
+
+    /// Sets how many typos keywords of the given polarity tolerate, [`Fuzziness::Exact`] by default.
+    /// Suffixing a single keyword with `~` or `~N` overrides this.
+    /// [`Polarity::Other`] tokens are never matched, so setting it has no effect.
+    ///
+    /// ## Example
+    /// ```
+    /// use kwp::{Fuzziness, Parser, Polarity, Prefixes};
+    ///
+    /// let mut parser = Parser::new("+jordan,-youth", Prefixes::default());
+    /// parser.set_fuzziness(Polarity::Positive, Fuzziness::AUTO);
+    ///
+    /// let keywords = parser.parse();
+    /// let products = vec!["Jordon 1 Retro", "Jordon 1 Retro Yuoth"];
+    /// let products = parser.match_products(products, keywords);
+    /// assert_eq!(products, vec!["Jordon 1 Retro", "Jordon 1 Retro Yuoth"]);
+    /// ```
+    pub fn set_fuzziness(&mut self, polarity: Polarity, fuzziness: Fuzziness) -> Fuzziness {
+        match polarity {
+            Polarity::Positive => self.defaults.positive_fuzziness = fuzziness,
+            Polarity::Negative => self.defaults.negative_fuzziness = fuzziness,
+            Polarity::Other => {}
+        }
+        fuzziness
+    }
+
+    /// Sets the product fields a `field:` qualifier may name, only `title` by default.
+    /// Keywords naming any other field, like `+http://example.com`, keep it as part of
+    /// their text. With `None`, any field name is accepted.
+    ///
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let mut parser = Parser::new("+vendor:nike,+ref:abc", Prefixes::default());
+    /// parser.set_fields(Some(&["title", "vendor"]));
+    ///
+    /// let keywords = parser.parse();
+    /// assert_eq!(keywords.positive[0].field.as_deref(), Some("vendor"));
+    /// assert_eq!(keywords.positive[1], "ref:abc");
+    /// assert_eq!(keywords.positive[1].field, None);
+    /// ```
+    pub fn set_fields(&mut self, fields: Option<&'a [&'a str]>) -> Option<&'a [&'a str]> {
+        self.defaults.fields = fields;
+        fields
+    }
+
+    /// Returns every setting of the parser.
+    /// ## Example
+    /// ```
+    /// use kwp::{MatchMode, Parser, Prefixes};
+    ///
+    /// let mut parser = Parser::new("+max", Prefixes::default());
+    /// parser.set_match_mode(MatchMode::Word);
+    ///
+    /// assert_eq!(parser.options().match_mode, MatchMode::Word);
+    /// assert!(!parser.options().retain_prefix);
+    /// ```
+    pub fn options(&self) -> Options {
+        Options {
+            retain_prefix: self.retain_prefix,
+            match_mode: self.defaults.mode,
+            positive_fuzziness: self.defaults.positive_fuzziness,
+            negative_fuzziness: self.defaults.negative_fuzziness,
+        }
+    }
+
+    /// Replaces every setting of the parser, e.g. with options loaded from a config file.
+    /// ## Example
+    /// ```
+    /// use kwp::{Fuzziness, Options, Parser, Prefixes};
+    ///
+    /// let mut parser = Parser::new("+jordan", Prefixes::default());
+    /// parser.set_options(Options {
+    ///     positive_fuzziness: Fuzziness::AUTO,
+    ///     ..Options::default()
+    /// });
+    ///
+    /// let keywords = parser.parse();
+    /// assert_eq!(keywords.positive[0].fuzziness, Fuzziness::AUTO);
+    /// ```
+    pub fn set_options(&mut self, options: Options) -> Options {
+        self.retain_prefix = options.retain_prefix;
+        self.defaults = Defaults {
+            mode: options.match_mode,
+            positive_fuzziness: options.positive_fuzziness,
+            negative_fuzziness: options.negative_fuzziness,
+            ..self.defaults
+        };
+        options
     }
 
-    /// Parses the input.
+    /// Returns the tokens of the input in the order they were written.
+    /// [`parse`](Self::parse) builds its keywords from them.
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Polarity, Prefixes};
+    ///
+    /// let parser = Parser::new("-bar,+foo", Prefixes::default());
+    /// let tokens: Vec<_> = parser.tokens().collect();
+    ///
+    /// assert_eq!(tokens[0].polarity, Polarity::Negative);
+    /// assert_eq!(tokens[0].text, "bar");
+    /// assert_eq!(tokens[1].raw, "+foo");
+    /// assert_eq!(tokens[1].span, 5..9);
+    /// ```
+    pub fn tokens(&self) -> Tokens<'a> {
+        Tokens::new(self.input, self.prefixes, self.defaults, self.retain_prefix)
+    }
+
+    /// Parses the input. An empty input has no keywords.
+    /// Keywords may be quoted (`+"air max 1, 86"`) and characters escaped with a backslash (`\+`).
+    /// Only the leading prefix is stripped, so `+c++` yields `c++`.
+    /// Unprefixed comparisons such as `price<200` become [`Predicate`]s, and size
+    /// predicates such as `size:uk8,eu42` become [`SizeFilter`]s.
     /// ## Example
     /// ```
     /// use kwp::{Parser, Prefixes};
     ///
-    /// let parser = Parser::new("+foo,-bar,-baz", Prefixes::default());
-    /// println!("{:#?}", parser.parse());
+    /// let parser = Parser::new(r#"+foo,-"1,000 pairs",\-baz"#, Prefixes::default());
+    /// let keywords = parser.parse();
+    ///
+    /// assert_eq!(keywords.positive, vec!["foo"]);
+    /// assert_eq!(keywords.negative, vec!["1,000 pairs"]);
+    /// assert_eq!(keywords.other, vec!["-baz"]);
     /// ```
     pub fn parse(&self) -> Keywords {
-        let split = self.input.clone();
-        let split = split.split(",");
+        self.parse_borrowed().into_owned()
+    }
 
-        let positive = self.parse_with_prefix(split.clone(), self.prefixes.positive);
-        let negative = self.parse_with_prefix(split.clone(), self.prefixes.negative);
+    /// Parses the input like [`parse`](Self::parse), but borrows keyword text from
+    /// the input instead of copying it. Only text that had quotes or escapes removed
+    /// is allocated.
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    /// use std::borrow::Cow;
+    ///
+    /// let input = String::from(r#"+foo,-"bar,baz""#);
+    /// let keywords = Parser::new(&input, Prefixes::default()).parse_borrowed();
+    ///
+    /// assert!(matches!(keywords.positive[0].text, Cow::Borrowed("foo")));
+    /// assert_eq!(keywords.negative[0].text, "bar,baz");
+    /// ```
+    pub fn parse_borrowed(&self) -> KeywordsRef<'a> {
+        let mut keywords = KeywordsRef::default();
+        for token in self.tokens() {
+            match token.kind {
+                TokenKind::Keyword(keyword) if token.polarity == Polarity::Negative => {
+                    keywords.negative.push(keyword)
+                }
+                TokenKind::Keyword(keyword) => keywords.positive.push(keyword),
+                TokenKind::Predicate(predicate) => keywords.predicates.push(predicate),
+                TokenKind::Size(sizes) => keywords.sizes.push(sizes),
+                TokenKind::SizeRange(range) => {
+                    if let Some(sizes) = keywords.sizes.last_mut() {
+                        sizes.ranges.push(range);
+                    }
+                }
+                TokenKind::Other => keywords.other.push(token.text),
+            }
+        }
+        keywords
+    }
 
-        let other = split
-            .filter(|x| {
-                !positive.iter().any(|y| x.contains(y)) && !negative.iter().any(|y| x.contains(y))
-            })
-            .map(|x| x.to_string())
-            .collect();
+    /// Parses the input, rejecting anything [`parse`](Self::parse) would put in `other`.
+    /// Empty keywords, dangling prefixes, missing prefixes, stray whitespace,
+    /// unterminated quotes and trailing escapes are all reported, each with the
+    /// byte span of the offending input. An empty input has no keywords.
+    /// ## Example
+    /// ```
+    /// use kwp::{ErrorCode, Parser, Prefixes};
+    ///
+    /// let parser = Parser::new("+foo,+, -bar", Prefixes::default());
+    /// let errors = parser.try_parse().unwrap_err();
+    ///
+    /// assert_eq!(errors[0].code, ErrorCode::DanglingPrefix);
+    /// assert_eq!(errors[0].span, 5..6);
+    /// assert_eq!(errors[1].code, ErrorCode::StrayWhitespace);
+    /// assert_eq!(errors[1].span, 7..8);
+    /// ```
+    pub fn try_parse(&self) -> Result<Keywords, ParseErrors> {
+        let mut errors = vec![];
+        for token in self.tokens() {
+            if matches!(token.kind, TokenKind::SizeRange(_)) && token.raw == token.raw.trim() {
+                continue;
+            }
+            errors.extend(self.check_token(token.span.start, token.raw));
+        }
 
-        return Keywords {
-            positive,
-            negative,
-            other,
-        };
+        errors.sort_by_key(|e| e.span.start);
+        if errors.is_empty() {
+            Ok(self.parse())
+        } else {
+            Err(ParseErrors(errors))
+        }
+    }
+
+    /// Checks a single raw token found at `start` for [`try_parse`](Self::try_parse).
+    fn check_token(&self, start: usize, raw: &str) -> Vec<ParseError> {
+        let mut errors = vec![];
+        let end = start + raw.len();
+        if raw.trim().is_empty() {
+            errors.push(ParseError::new(
+                start..end,
+                ErrorCode::EmptyKeyword,
+                "empty keyword",
+            ));
+            return errors;
+        }
+
+        let leading = raw.len() - raw.trim_start().len();
+        let trailing = raw.len() - raw.trim_end().len();
+        if leading > 0 {
+            errors.push(ParseError::new(
+                start..start + leading,
+                ErrorCode::StrayWhitespace,
+                "whitespace before keyword",
+            ));
+        }
+        let trimmed = raw.trim();
+        let start = start + leading;
+
+        match self.prefixes.strip(trimmed) {
+            Some((polarity, prefix)) => {
+                let body = &trimmed[prefix.len()..];
+                let body_leading = body.len() - body.trim_start().len();
+                if body.trim_start().is_empty() {
+                    errors.push(ParseError::new(
+                        start..start + prefix.len(),
+                        ErrorCode::DanglingPrefix,
+                        format!("expected a keyword after `{}`", prefix),
+                    ));
+                } else if body_leading > 0 {
+                    errors.push(ParseError::new(
+                        start + prefix.len()..start + prefix.len() + body_leading,
+                        ErrorCode::StrayWhitespace,
+                        format!("whitespace after `{}`", prefix),
+                    ));
+                } else {
+                    match Keyword::parse(body, self.defaults, polarity) {
+                        Ok(keyword) if keyword.text.is_empty() => errors.push(ParseError::new(
+                            start..start + trimmed.len(),
+                            ErrorCode::EmptyKeyword,
+                            "expected a keyword after its modifiers",
+                        )),
+                        Ok(_) => {}
+                        Err(error) => errors.push(error.offset(start + prefix.len())),
+                    }
+                }
+            }
+            None => {
+                let predicate = match SizeFilter::parse(trimmed) {
+                    Some(sizes) => Some(sizes.map(drop)),
+                    None => Predicate::parse(trimmed).map(|predicate| predicate.map(drop)),
+                };
+                match predicate {
+                    Some(Ok(())) => {}
+                    Some(Err(error)) => errors.push(error.offset(start)),
+                    None => errors.push(ParseError::new(
+                        start..start + trimmed.len(),
+                        ErrorCode::MissingPrefix,
+                        format!(
+                            "expected `{}` or `{}` before keyword",
+                            self.prefixes.positive, self.prefixes.negative
+                        ),
+                    )),
+                }
+            }
+        }
+
+        if trailing > 0 {
+            errors.push(ParseError::new(
+                end - trailing..end,
+                ErrorCode::StrayWhitespace,
+                "whitespace after keyword",
+            ));
+        }
+        errors.extend(lexer::check(trimmed, start));
+        errors
+    }
+
+    /// Parses the input as a boolean expression.
+    /// `,` requires every term, `|` requires any of them and parentheses group terms.
+    /// ## Example
+    /// ```
+    /// use kwp::{Expr, Parser, Prefixes};
+    ///
+    /// let parser = Parser::new("(+jordan|+dunk),+low", Prefixes::default());
+    /// let expr = parser.parse_expr().unwrap();
+    ///
+    /// assert_eq!(
+    ///     expr,
+    ///     Expr::And(vec![
+    ///         Expr::Or(vec![Expr::Term("jordan".into()), Expr::Term("dunk".into())]),
+    ///         Expr::Term("low".into()),
+    ///     ])
+    /// );
+    /// ```
+    pub fn parse_expr(&self) -> Result<Expr, ParseErrors> {
+        Ok(ExprParser::new(self.input, self.prefixes, self.defaults).parse()?)
     }
 
     /// Finds products that match the provided positive & negative keywords.  
@@ -154,21 +713,41 @@ impl<'a> Parser<'a> {
     /// assert_eq!(products, vec!["MyProduct Adult"]);
     /// ```
     pub fn match_products(&self, products: Vec<&str>, keywords: Keywords) -> Vec<String> {
-        let mut found: Vec<String> = vec![];
+        self.match_products_with_distance(products, keywords)
+            .into_iter()
+            .map(|(product, _)| product)
+            .collect()
+    }
+
+    /// Like [`match_products`](Self::match_products), but pairs every product with
+    /// the edit distance of its closest positive keyword.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let products = vec!["Blurple Hooide", "Blurple Hoodie", "Blurple Tee"];
+    /// let parser = Parser::new("+hoodie~", Prefixes::default());
+    /// let keywords = parser.parse();
+    ///
+    /// let products = parser.match_products_with_distance(products, keywords);
+    /// assert_eq!(
+    ///     products,
+    ///     vec![("Blurple Hooide".to_string(), 1), ("Blurple Hoodie".to_string(), 0)]
+    /// );
+    /// ```
+    pub fn match_products_with_distance(
+        &self,
+        products: Vec<&str>,
+        keywords: Keywords,
+    ) -> Vec<(String, usize)> {
+        let matcher = keywords.compile();
+        let mut found: Vec<(String, usize)> = vec![];
         for product in products {
-            let p_lower = &product.to_lowercase();
-            if keywords
-                .positive
-                .iter()
-                .any(|e| p_lower.to_lowercase().contains(&e.to_lowercase()))
-                && !keywords
-                    .negative
-                    .iter()
-                    .any(|e| p_lower.contains(&e.to_lowercase()))
-            {
-                found.push(product.to_string());
+            if let Some(distance) = matcher.distance(product) {
+                found.push((product.to_string(), distance));
             }
         }
-        return found;
+        found
     }
 }
@@ -0,0 +1,171 @@
+use std::borrow::Cow;
+
+/// A product keywords can be matched against, made of named text fields.
+///
+/// Plain strings are products with a single `title` field. A [`Matcher`](crate::Matcher)
+/// looks at the fields set with [`Matcher::set_fields`](crate::Matcher::set_fields),
+/// matching each of them on its own so a keyword never spans two fields.
+/// ## Example
+/// ```
+/// use kwp::{Matchable, Parser, Prefixes};
+/// use std::borrow::Cow;
+///
+/// struct Product {
+///     title: String,
+///     vendor: String,
+///     tags: Vec<String>,
+/// }
+///
+/// impl Matchable for Product {
+///     fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+///         match name {
+///             "title" => Some(Cow::Borrowed(&self.title)),
+///             "vendor" => Some(Cow::Borrowed(&self.vendor)),
+///             "tags" => Some(Cow::Owned(self.tags.join(", "))),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let product = Product {
+///     title: "Dunk Low".to_string(),
+///     vendor: "Nike".to_string(),
+///     tags: vec!["restock".to_string()],
+/// };
+/// let mut matcher = Parser::new("+nike", Prefixes::default()).parse().compile();
+/// assert!(!matcher.is_match(&product));
+///
+/// matcher.set_fields(&["title", "vendor"]);
+/// assert!(matcher.is_match(&product));
+/// ```
+pub trait Matchable {
+    /// Returns the text of the field called `name`, or `None` when the product has no such field.
+    fn field(&self, name: &str) -> Option<Cow<'_, str>>;
+
+    /// Returns the numeric value of the field called `name`, checked by [`Predicate`](crate::Predicate)s.
+    /// By default the field text is parsed as a number, ignoring surrounding whitespace.
+    fn number(&self, name: &str) -> Option<f64> {
+        self.field(name)?.trim().parse().ok()
+    }
+}
+
+impl Matchable for str {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "title" => Some(Cow::Borrowed(self)),
+            _ => None,
+        }
+    }
+}
+
+impl Matchable for String {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.as_str().field(name)
+    }
+}
+
+impl<T: Matchable + ?Sized> Matchable for &T {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        (**self).field(name)
+    }
+
+    fn number(&self, name: &str) -> Option<f64> {
+        (**self).number(name)
+    }
+}
+
+/// A product made of variants, such as sizes, colorways or widths, that are
+/// matched one by one with [`Matcher::match_variants`](crate::Matcher::match_variants).
+///
+/// Each variant is matched as a [`Listing`], whose fields combine the product's
+/// text with the variant's, so a negative keyword like `-wide` that only hits
+/// one variant rejects that variant alone.
+pub trait Variants: Matchable {
+    type Variant: Matchable;
+
+    /// Returns the variants of the product.
+    fn variants(&self) -> &[Self::Variant];
+}
+
+/// A variant together with its product, matched as a single product.
+///
+/// A field the product and the variant both have is their texts on separate
+/// lines, otherwise it is the text of whichever has it. Plain keywords never
+/// span the line break, but globs, regexes and fuzzy keywords may, so `+dunk*wide`
+/// matches a `Dunk Low` product's `9 Wide` variant. Numbers, such as a variant's
+/// own price, are read from the variant first.
+///
+/// The `size` field is the variant's own, or its title when it has none, as for
+/// plain string variants, and the product's size only when the variant has neither.
+#[derive(Debug)]
+pub struct Listing<'p, P: Variants + ?Sized> {
+    pub product: &'p P,
+    pub variant: &'p P::Variant,
+}
+
+impl<P: Variants + ?Sized> Clone for Listing<'_, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Variants + ?Sized> Copy for Listing<'_, P> {}
+
+impl<P: Variants + ?Sized> Matchable for Listing<'_, P> {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        if name == "size" {
+            return self
+                .variant
+                .field("size")
+                .or_else(|| self.variant.field("title"))
+                .or_else(|| self.product.field("size"));
+        }
+        match (self.product.field(name), self.variant.field(name)) {
+            (Some(product), Some(variant)) => Some(Cow::Owned(format!("{}\n{}", product, variant))),
+            (product, variant) => product.or(variant),
+        }
+    }
+
+    fn number(&self, name: &str) -> Option<f64> {
+        self.variant
+            .number(name)
+            .or_else(|| self.product.number(name))
+    }
+}
+
+/// A product and its variants, for products that don't implement [`Variants`] themselves.
+/// ## Example
+/// ```
+/// use kwp::{Parser, Prefixes, WithVariants};
+///
+/// let product = WithVariants {
+///     product: "New Balance 990v6",
+///     variants: vec!["Grey / 9 / D", "Grey / 9 / 2E Wide"],
+/// };
+/// let matcher = Parser::new("+990,-wide", Prefixes::default()).parse().compile();
+///
+/// assert_eq!(matcher.match_variants(&product), vec![&"Grey / 9 / D"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WithVariants<P, V> {
+    pub product: P,
+    pub variants: Vec<V>,
+}
+
+impl<P: Matchable, V> Matchable for WithVariants<P, V> {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.product.field(name)
+    }
+
+    fn number(&self, name: &str) -> Option<f64> {
+        self.product.number(name)
+    }
+}
+
+impl<P: Matchable, V: Matchable> Variants for WithVariants<P, V> {
+    type Variant = V;
+
+    fn variants(&self) -> &[V] {
+        &self.variants
+    }
+}
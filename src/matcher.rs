@@ -0,0 +1,669 @@
+use crate::keyword::Haystack;
+use crate::{
+    Filter, Keyword, Keywords, Listing, MatchMode, Matchable, Pattern, Polarity, Predicate,
+    SizeFilter, Variants,
+};
+use aho_corasick::AhoCorasick;
+use std::ops::Range;
+
+/// A keyword prepared for matching.
+#[derive(Debug, Clone)]
+struct Entry {
+    keyword: Keyword,
+    /// The lowercased keyword text.
+    lower: String,
+    /// The keyword's pattern, with globs lowercased.
+    pattern: Option<Pattern>,
+    /// Index of the keyword's field in [`Matcher::fields`], `None` when it has none.
+    field: Option<usize>,
+}
+
+impl Entry {
+    fn new(keyword: &Keyword) -> Self {
+        Self {
+            lower: keyword.text.to_lowercase(),
+            pattern: keyword.pattern.as_ref().map(Pattern::lowercase),
+            keyword: keyword.clone(),
+            field: None,
+        }
+    }
+
+    /// Whether the entry is matched against the field at `field`.
+    fn accepts(&self, field: usize, defaults: usize) -> bool {
+        match self.field {
+            Some(own) => own == field,
+            None => field < defaults,
+        }
+    }
+
+    /// Whether the entry is found by the automaton rather than on its own.
+    fn is_plain(&self) -> bool {
+        self.keyword.pattern.is_none()
+    }
+}
+
+/// Where a keyword was found in a product.
+#[derive(Debug, Clone)]
+struct Hit {
+    /// Index of the field in [`Matcher::fields`].
+    field: usize,
+    /// Byte range in the lowercased field, `None` for fuzzy matches.
+    range: Option<Range<usize>>,
+    distance: usize,
+}
+
+/// The first exact hit of every plain keyword in a product.
+struct Hits {
+    positive: Vec<Option<Hit>>,
+    negative: Vec<Option<Hit>>,
+}
+
+/// The fields of a product, indexed like [`Matcher::fields`], `None` where it has no such field.
+type Haystacks<'p> = Vec<Option<Haystack<'p>>>;
+
+/// A keyword that hit a product, as reported by [`Matcher::explain`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeywordHit<'m> {
+    pub keyword: &'m Keyword,
+    /// The field the keyword hit.
+    pub field: &'m str,
+    /// Byte range of the hit in the original field text, `None` for fuzzy matches.
+    pub range: Option<Range<usize>>,
+    /// Edit distance of the hit, `0` unless the keyword is fuzzy.
+    pub distance: usize,
+}
+
+/// Why a product was accepted or rejected, as reported by [`Matcher::explain`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation<'m> {
+    /// Whether the product matches.
+    pub matched: bool,
+    /// Every positive keyword that hit, in keyword order.
+    pub positive: Vec<KeywordHit<'m>>,
+    /// Every negative keyword that hit, in keyword order. Any of them vetoes the product.
+    pub negative: Vec<KeywordHit<'m>>,
+    /// Every predicate the product doesn't satisfy. Any of them rejects the product.
+    pub failed: Vec<&'m Predicate>,
+    /// Every size predicate the product doesn't satisfy. Any of them rejects the product.
+    pub failed_sizes: Vec<&'m SizeFilter>,
+}
+
+/// A product with variants that matched, as reported by [`Matcher::match_listings`].
+#[derive(Debug)]
+pub struct VariantMatch<'p, P: Variants + ?Sized> {
+    pub product: &'p P,
+    /// The variants that matched, in their original order. Empty for products without variants.
+    pub variants: Vec<&'p P::Variant>,
+}
+
+impl<P: Variants + ?Sized> Clone for VariantMatch<'_, P> {
+    fn clone(&self) -> Self {
+        Self {
+            product: self.product,
+            variants: self.variants.clone(),
+        }
+    }
+}
+
+impl<P, V> PartialEq for VariantMatch<'_, P>
+where
+    P: Variants<Variant = V> + PartialEq + ?Sized,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.product == other.product && self.variants == other.variants
+    }
+}
+
+/// Compiled [`Keywords`] that scan each product in a single pass.
+///
+/// Every plain keyword is lowercased once and searched for with a single
+/// Aho-Corasick automaton, so a product is lowercased and scanned once no matter
+/// how many keywords there are. Patterns and fuzzy matches are only evaluated
+/// when the automaton didn't already decide the result.
+/// Created by [`Keywords::compile`].
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    positive: Vec<Entry>,
+    negative: Vec<Entry>,
+    automaton: AhoCorasick,
+    /// Maps automaton pattern ids to entries: positive ones first, then negative ones.
+    ids: Vec<(Polarity, usize)>,
+    /// The product fields keywords are matched against: the default fields first,
+    /// then the ones only named by field-scoped keywords.
+    fields: Vec<String>,
+    /// How many of `fields` are default fields.
+    defaults: usize,
+    predicates: Vec<Predicate>,
+    sizes: Vec<SizeFilter>,
+}
+
+impl Matcher {
+    pub(crate) fn new(keywords: &Keywords) -> Self {
+        let positive: Vec<Entry> = keywords.positive.iter().map(Entry::new).collect();
+        let negative: Vec<Entry> = keywords.negative.iter().map(Entry::new).collect();
+
+        let mut ids = vec![];
+        let mut patterns = vec![];
+        for (polarity, entries) in [
+            (Polarity::Positive, &positive),
+            (Polarity::Negative, &negative),
+        ] {
+            for (i, entry) in entries.iter().enumerate() {
+                if entry.is_plain() {
+                    ids.push((polarity, i));
+                    patterns.push(entry.lower.as_str());
+                }
+            }
+        }
+        let automaton = AhoCorasick::new(patterns).expect("keyword automaton is too large");
+
+        let mut matcher = Self {
+            positive,
+            negative,
+            automaton,
+            ids,
+            fields: vec![],
+            defaults: 0,
+            predicates: keywords.predicates.clone(),
+            sizes: keywords.sizes.clone(),
+        };
+        matcher.index_fields(&["title"]);
+        matcher
+    }
+
+    /// Sets which [`Matchable`] fields keywords without a `field:` qualifier are
+    /// matched against, only `title` by default.
+    /// A keyword hits the product when it hits any of these fields.
+    ///
+    /// ## Example
+    /// ```
+    /// use kwp::{Matchable, Parser, Prefixes};
+    /// use std::borrow::Cow;
+    ///
+    /// struct Product(&'static str, &'static str);
+    ///
+    /// impl Matchable for Product {
+    ///     fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+    ///         match name {
+    ///             "title" => Some(Cow::Borrowed(self.0)),
+    ///             "sku" => Some(Cow::Borrowed(self.1)),
+    ///             _ => None,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut matcher = Parser::new("+dd1391", Prefixes::default()).parse().compile();
+    /// matcher.set_fields(&["title", "sku"]);
+    /// assert!(matcher.is_match(&Product("Dunk Low", "DD1391-100")));
+    /// ```
+    pub fn set_fields(&mut self, fields: &[&str]) {
+        self.index_fields(fields);
+    }
+
+    /// The fields keywords without a `field:` qualifier are matched against.
+    pub fn fields(&self) -> &[String] {
+        &self.fields[..self.defaults]
+    }
+
+    /// Lists the default fields followed by every other field a keyword is scoped to,
+    /// and points each entry at its field.
+    fn index_fields(&mut self, defaults: &[&str]) {
+        let fields = &mut self.fields;
+        *fields = defaults.iter().map(|field| field.to_string()).collect();
+        self.defaults = fields.len();
+        for entry in self.positive.iter_mut().chain(self.negative.iter_mut()) {
+            entry.field = entry.keyword.field.as_ref().map(|name| {
+                fields
+                    .iter()
+                    .position(|field| field == name)
+                    .unwrap_or_else(|| {
+                        fields.push(name.clone());
+                        fields.len() - 1
+                    })
+            });
+        }
+    }
+
+    /// Checks whether `product` matches any positive keyword and no negative one,
+    /// and satisfies every [`Predicate`] and [`SizeFilter`]. Without positive keywords,
+    /// predicates and size filters alone select the products that satisfy them.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let matcher = Parser::new("+hoodie,-youth", Prefixes::default()).parse().compile();
+    /// assert!(matcher.is_match("Blurple Hoodie"));
+    /// assert!(!matcher.is_match("Blurple Hoodie (Youth)"));
+    /// ```
+    pub fn is_match<P: Matchable + ?Sized>(&self, product: &P) -> bool {
+        self.distance(product).is_some()
+    }
+
+    /// Like [`is_match`](Self::is_match), but returns the edit distance of the
+    /// closest positive keyword when the product matches.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let matcher = Parser::new("+hoodie~", Prefixes::default()).parse().compile();
+    /// assert_eq!(matcher.distance("Blurple Hooide"), Some(1));
+    /// ```
+    pub fn distance<P: Matchable + ?Sized>(&self, product: &P) -> Option<usize> {
+        let haystacks = self.haystacks(product);
+        let hits = self.hits(&haystacks);
+        if self.is_vetoed(&hits, &haystacks) || !self.satisfies(product) {
+            return None;
+        }
+
+        if self.filters_only() || hits.positive.iter().any(Option::is_some) {
+            return Some(0);
+        }
+        self.positive
+            .iter()
+            .filter_map(|entry| self.locate(entry, &haystacks))
+            .map(|hit| hit.distance)
+            .min()
+    }
+
+    /// Scores how relevant `product` is, or returns `None` when it doesn't match.
+    ///
+    /// Every positive keyword that hits adds its [weight](Keyword::weight), plus up to
+    /// half its weight the closer to the start of the product it appears, plus half its
+    /// weight again when it lines up with word boundaries. Fuzzy hits only add their
+    /// weight, divided by one more than their edit distance. Products selected by
+    /// predicates and size filters alone score 0.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let matcher = Parser::new("+dunk,+low^2", Prefixes::default()).parse().compile();
+    /// let both = matcher.score("Nike Dunk Low").unwrap();
+    /// let one = matcher.score("Nike Dunk High").unwrap();
+    ///
+    /// assert!(both > one);
+    /// assert_eq!(matcher.score("Nike Air Max"), None);
+    /// ```
+    pub fn score<P: Matchable + ?Sized>(&self, product: &P) -> Option<f64> {
+        let haystacks = self.haystacks(product);
+        let mut hits = self.hits(&haystacks);
+        if self.is_vetoed(&hits, &haystacks) || !self.satisfies(product) {
+            return None;
+        }
+        if self.filters_only() {
+            return Some(0.0);
+        }
+
+        let mut score = None;
+        for (entry, hit) in self.positive.iter().zip(hits.positive.iter_mut()) {
+            let hit = match hit.take().or_else(|| self.locate(entry, &haystacks)) {
+                Some(hit) => hit,
+                None => continue,
+            };
+            let weight = entry.keyword.weight;
+            let mut points = weight;
+            if let Some(range) = hit.range {
+                let haystack = haystacks[hit.field].as_ref().expect("hit field exists");
+                let bounds = haystack.bounds();
+                let on_bound = |i: usize| bounds.binary_search(&i).is_ok();
+
+                let len = haystack.lower.len().max(1) as f64;
+                points += weight * 0.5 * (1.0 - range.start as f64 / len);
+                if on_bound(range.start) && on_bound(range.end) {
+                    points += weight * 0.5;
+                }
+            }
+            *score.get_or_insert(0.0) += points / (1 + hit.distance) as f64;
+        }
+        score
+    }
+
+    /// Scores every product and returns the matching ones, most relevant first.
+    /// Products with equal scores keep their original order.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let products = vec!["Dunk High", "Dunk Low", "Air Max"];
+    /// let matcher = Parser::new("+dunk,+low", Prefixes::default()).parse().compile();
+    ///
+    /// let ranked: Vec<_> = matcher.rank(&products).into_iter().map(|(p, _)| p).collect();
+    /// assert_eq!(ranked, vec!["Dunk Low", "Dunk High"]);
+    /// ```
+    pub fn rank<'p>(&self, products: &[&'p str]) -> Vec<(&'p str, f64)> {
+        let mut ranked: Vec<(&'p str, f64)> = products
+            .iter()
+            .filter_map(|product| self.score(product).map(|score| (*product, score)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+    }
+
+    /// Returns the most relevant matching product, the first one on ties.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let products = vec!["Dunk High", "Dunk Low", "Air Max"];
+    /// let matcher = Parser::new("+dunk,+low", Prefixes::default()).parse().compile();
+    ///
+    /// assert_eq!(matcher.best(&products), Some("Dunk Low"));
+    /// ```
+    pub fn best<'p>(&self, products: &[&'p str]) -> Option<&'p str> {
+        self.rank(products).first().map(|(product, _)| *product)
+    }
+
+    /// Finds the products that match, in their original order.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let products = vec!["MyProduct Adult", "MyProduct Youth"];
+    /// let matcher = Parser::new("+myproduct,-youth", Prefixes::default()).parse().compile();
+    ///
+    /// assert_eq!(matcher.match_products(&products), vec!["MyProduct Adult"]);
+    /// ```
+    pub fn match_products<'p>(&self, products: &[&'p str]) -> Vec<&'p str> {
+        products
+            .iter()
+            .copied()
+            .filter(|product| self.is_match(product))
+            .collect()
+    }
+
+    /// Finds the [`Matchable`] products that match, as references into `products`
+    /// in their original order.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let products = vec![String::from("MyProduct Adult"), String::from("MyProduct Youth")];
+    /// let matcher = Parser::new("+myproduct,-youth", Prefixes::default()).parse().compile();
+    ///
+    /// assert_eq!(matcher.match_items(&products), vec![&products[0]]);
+    /// ```
+    pub fn match_items<'p, P: Matchable>(&self, products: &'p [P]) -> Vec<&'p P> {
+        products
+            .iter()
+            .filter(|product| self.is_match(*product))
+            .collect()
+    }
+
+    /// Lazily keeps the products that match, in their original order. Unlike
+    /// [`match_items`](Self::match_items), products are only checked as the
+    /// returned iterator reaches them, so they never have to be collected.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let matcher = Parser::new("+myproduct,-youth", Prefixes::default()).parse().compile();
+    /// let products = (0..).map(|i| format!("MyProduct {}", if i % 2 == 0 { "Adult" } else { "Youth" }));
+    ///
+    /// let first: Vec<String> = matcher.filter(products).take(2).collect();
+    /// assert_eq!(first, vec!["MyProduct Adult", "MyProduct Adult"]);
+    /// ```
+    pub fn filter<I>(&self, products: I) -> Filter<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: Matchable,
+    {
+        Filter::new(self, products.into_iter())
+    }
+
+    /// Finds the variants of `product` that match, in their original order. Each
+    /// variant is matched as a [`Listing`], combining the product's text with its own.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes, WithVariants};
+    ///
+    /// let product = WithVariants {
+    ///     product: "Nike Dunk Low",
+    ///     variants: vec!["Panda / 9", "Panda / 9 Wide", "Grey Fog / 10"],
+    /// };
+    /// let matcher = Parser::new("+panda,-wide", Prefixes::default()).parse().compile();
+    ///
+    /// assert_eq!(matcher.match_variants(&product), vec![&"Panda / 9"]);
+    /// ```
+    pub fn match_variants<'p, P: Variants + ?Sized>(&self, product: &'p P) -> Vec<&'p P::Variant> {
+        product
+            .variants()
+            .iter()
+            .filter(|variant| self.is_match(&Listing { product, variant }))
+            .collect()
+    }
+
+    /// Finds the products with a matching variant, in their original order, along
+    /// with the variants that matched, see [`match_variants`](Self::match_variants).
+    /// Products without variants match on their own.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes, WithVariants};
+    ///
+    /// let products = vec![
+    ///     WithVariants { product: "Nike Dunk Low", variants: vec!["9", "9 Wide"] },
+    ///     WithVariants { product: "Nike Dunk High", variants: vec!["10 Wide"] },
+    ///     WithVariants { product: "Dunk Low Gift Card", variants: vec![] },
+    /// ];
+    /// let matcher = Parser::new("+dunk,-wide", Prefixes::default()).parse().compile();
+    /// let matches = matcher.match_listings(&products);
+    ///
+    /// assert_eq!(matches.len(), 2);
+    /// assert_eq!(matches[0].product.product, "Nike Dunk Low");
+    /// assert_eq!(matches[0].variants, vec![&"9"]);
+    /// assert!(matches[1].variants.is_empty());
+    /// ```
+    pub fn match_listings<'p, P: Variants>(&self, products: &'p [P]) -> Vec<VariantMatch<'p, P>> {
+        products
+            .iter()
+            .filter_map(|product| {
+                let variants = self.match_variants(product);
+                let matched = if product.variants().is_empty() {
+                    self.is_match(product)
+                } else {
+                    !variants.is_empty()
+                };
+                if matched {
+                    Some(VariantMatch { product, variants })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Like [`match_items`](Self::match_items), but returns the indices of the matching products.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let products = vec!["MyProduct Adult", "MyProduct Youth", "MyProduct Kids"];
+    /// let matcher = Parser::new("+myproduct,-youth", Prefixes::default()).parse().compile();
+    ///
+    /// assert_eq!(matcher.match_indices(&products), vec![0, 2]);
+    /// ```
+    pub fn match_indices<P: Matchable>(&self, products: &[P]) -> Vec<usize> {
+        products
+            .iter()
+            .enumerate()
+            .filter(|(_, product)| self.is_match(*product))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Reports which keywords hit `product` and where, and whether it matches.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let matcher = Parser::new("+hoodie,+crew,-youth", Prefixes::default()).parse().compile();
+    /// let explanation = matcher.explain("Blurple Hoodie (Youth)");
+    ///
+    /// assert!(!explanation.matched);
+    /// assert_eq!(explanation.positive[0].keyword, "hoodie");
+    /// assert_eq!(explanation.positive[0].range, Some(8..14));
+    /// assert_eq!(explanation.negative[0].keyword, "youth");
+    /// assert_eq!(explanation.negative[0].range, Some(16..21));
+    /// ```
+    pub fn explain<'m, P: Matchable + ?Sized>(&'m self, product: &P) -> Explanation<'m> {
+        let haystacks = self.haystacks(product);
+        let hits = self.hits(&haystacks);
+        let explain = |entries: &'m [Entry], hits: Vec<Option<Hit>>| {
+            entries
+                .iter()
+                .zip(hits)
+                .filter_map(|(entry, hit)| {
+                    let hit = hit.or_else(|| self.locate(entry, &haystacks))?;
+                    let haystack = haystacks[hit.field].as_ref().expect("hit field exists");
+                    Some(KeywordHit {
+                        keyword: &entry.keyword,
+                        field: &self.fields[hit.field],
+                        range: hit.range.map(|range| haystack.to_text(range)),
+                        distance: hit.distance,
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let positive = explain(&self.positive, hits.positive);
+        let negative = explain(&self.negative, hits.negative);
+        let failed: Vec<&Predicate> = self
+            .predicates
+            .iter()
+            .filter(|predicate| !predicate.is_match(product))
+            .collect();
+        let failed_sizes: Vec<&SizeFilter> = self
+            .sizes
+            .iter()
+            .filter(|sizes| !sizes.is_match(product))
+            .collect();
+        Explanation {
+            matched: (self.filters_only() || !positive.is_empty())
+                && negative.is_empty()
+                && failed.is_empty()
+                && failed_sizes.is_empty(),
+            positive,
+            negative,
+            failed,
+            failed_sizes,
+        }
+    }
+
+    /// Looks up every field the keywords are matched against.
+    fn haystacks<'p, P: Matchable + ?Sized>(&self, product: &'p P) -> Haystacks<'p> {
+        self.fields
+            .iter()
+            .map(|field| product.field(field).map(Haystack::new))
+            .collect()
+    }
+
+    /// Runs the automaton over every field once, recording the first field in
+    /// which each plain keyword occurs exactly.
+    fn hits(&self, haystacks: &[Option<Haystack>]) -> Hits {
+        let mut hits = Hits {
+            positive: vec![None; self.positive.len()],
+            negative: vec![None; self.negative.len()],
+        };
+        for (field, haystack) in haystacks.iter().enumerate() {
+            let haystack = match haystack {
+                Some(haystack) => haystack,
+                None => continue,
+            };
+            for found in self.automaton.find_overlapping_iter(&haystack.lower) {
+                let (polarity, i) = self.ids[found.pattern().as_usize()];
+                let (entries, hit) = match polarity {
+                    Polarity::Positive => (&self.positive, &mut hits.positive[i]),
+                    _ => (&self.negative, &mut hits.negative[i]),
+                };
+                if hit.is_some() {
+                    continue;
+                }
+                if !entries[i].accepts(field, self.defaults) {
+                    continue;
+                }
+                let accepted = match entries[i].keyword.mode {
+                    MatchMode::Substring => true,
+                    MatchMode::Word => {
+                        let bounds = haystack.bounds();
+                        bounds.binary_search(&found.start()).is_ok()
+                            && bounds.binary_search(&found.end()).is_ok()
+                    }
+                };
+                if accepted {
+                    *hit = Some(Hit {
+                        field,
+                        range: Some(found.range()),
+                        distance: 0,
+                    });
+                }
+            }
+        }
+        hits
+    }
+
+    /// Whether the product satisfies every predicate and size predicate.
+    fn satisfies<P: Matchable + ?Sized>(&self, product: &P) -> bool {
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.is_match(product))
+            && self.sizes.iter().all(|sizes| sizes.is_match(product))
+    }
+
+    /// Whether there are predicates or size filters but no positive keyword to match.
+    fn filters_only(&self) -> bool {
+        self.positive.is_empty() && !(self.predicates.is_empty() && self.sizes.is_empty())
+    }
+
+    /// Whether any negative keyword hits the product.
+    fn is_vetoed(&self, hits: &Hits, haystacks: &[Option<Haystack>]) -> bool {
+        self.negative
+            .iter()
+            .zip(&hits.negative)
+            .any(|(entry, hit)| hit.is_some() || self.locate(entry, haystacks).is_some())
+    }
+
+    /// Finds an entry the automaton didn't find exactly: the first field a pattern
+    /// occurs in, or the field closest to a fuzzy keyword.
+    fn locate(&self, entry: &Entry, haystacks: &[Option<Haystack>]) -> Option<Hit> {
+        let fields = haystacks
+            .iter()
+            .enumerate()
+            .filter(|(field, _)| entry.accepts(*field, self.defaults))
+            .filter_map(|(field, haystack)| haystack.as_ref().map(|haystack| (field, haystack)));
+        match &entry.pattern {
+            Some(pattern) => fields
+                .filter_map(|(field, haystack)| {
+                    let range = pattern.find(haystack, entry.keyword.mode)?;
+                    Some(Hit {
+                        field,
+                        range: Some(range),
+                        distance: 0,
+                    })
+                })
+                .next(),
+            None => fields
+                .filter_map(|(field, haystack)| {
+                    let distance = entry
+                        .keyword
+                        .fuzzy_distance(&haystack.lower, &entry.lower)?;
+                    Some(Hit {
+                        field,
+                        range: None,
+                        distance,
+                    })
+                })
+                .min_by_key(|hit| hit.distance),
+        }
+    }
+}
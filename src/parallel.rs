@@ -0,0 +1,83 @@
+//! Matching products across threads with [`rayon`].
+//!
+//! A [`Matcher`] is only read while matching, so every worker shares the same
+//! compiled keywords by reference.
+
+use crate::{Matchable, Matcher};
+use rayon::prelude::*;
+
+impl Matcher {
+    /// Like [`match_products`](Self::match_products), but checks the products in parallel.
+    /// The result keeps the order of `products`.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let products = vec!["MyProduct Adult", "MyProduct Youth", "MyProduct Kids"];
+    /// let matcher = Parser::new("+myproduct,-youth", Prefixes::default()).parse().compile();
+    ///
+    /// assert_eq!(
+    ///     matcher.par_match_products(&products),
+    ///     vec!["MyProduct Adult", "MyProduct Kids"]
+    /// );
+    /// ```
+    pub fn par_match_products<'p>(&self, products: &[&'p str]) -> Vec<&'p str> {
+        self.par_filter(products.par_iter().copied()).collect()
+    }
+
+    /// Like [`match_items`](Self::match_items), but checks the products in parallel.
+    /// The result keeps the order of `products`.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let products = vec![String::from("MyProduct Adult"), String::from("MyProduct Youth")];
+    /// let matcher = Parser::new("+myproduct,-youth", Prefixes::default()).parse().compile();
+    ///
+    /// assert_eq!(matcher.par_match_items(&products), vec![&products[0]]);
+    /// ```
+    pub fn par_match_items<'p, P: Matchable + Sync>(&self, products: &'p [P]) -> Vec<&'p P> {
+        self.par_filter(products).collect()
+    }
+
+    /// Like [`match_indices`](Self::match_indices), but checks the products in parallel.
+    /// ⚠️ Case insensitive
+    pub fn par_match_indices<P: Matchable + Sync>(&self, products: &[P]) -> Vec<usize> {
+        products
+            .par_iter()
+            .enumerate()
+            .filter(|(_, product)| self.is_match(*product))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Keeps the products of a parallel iterator that match, see [`filter`](Self::filter).
+    /// Collecting the result keeps the order of the products.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    /// use rayon::prelude::*;
+    ///
+    /// let matcher = Parser::new("+myproduct,-youth", Prefixes::default()).parse().compile();
+    /// let products = (0..1000).into_par_iter().map(|i| {
+    ///     format!("MyProduct {} {}", i, if i % 2 == 0 { "Adult" } else { "Youth" })
+    /// });
+    ///
+    /// let matched: Vec<String> = matcher.par_filter(products).collect();
+    /// assert_eq!(matched.len(), 500);
+    /// assert_eq!(matched[1], "MyProduct 2 Adult");
+    /// ```
+    pub fn par_filter<'m, I>(&'m self, products: I) -> impl ParallelIterator<Item = I::Item> + 'm
+    where
+        I: IntoParallelIterator,
+        I::Iter: 'm,
+        I::Item: Matchable,
+    {
+        products
+            .into_par_iter()
+            .filter(move |product| self.is_match(product))
+    }
+}
@@ -0,0 +1,151 @@
+use crate::keyword::Haystack;
+use crate::{lexer, Glob, MatchMode};
+use std::borrow::Cow;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::ops::Range;
+
+/// A keyword that is matched as a pattern rather than as plain text.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "PatternRepr", try_from = "PatternRepr")
+)]
+pub enum Pattern {
+    /// A keyword containing `*` or `?` wildcards.
+    Glob(Glob),
+    /// A keyword written as `/pattern/flags`, with `,` and `"` escaped as `\,` and `\"`.
+    /// Unlike every other keyword it is case sensitive unless the `i` flag is given.
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    /// Parses `/pattern/flags`, or returns `None` when `raw` is not written that way.
+    /// An empty pattern is an error, since it would match every product.
+    /// Flags are folded into the returned source, so `/foo/i` compiles to `(?i)foo`,
+    /// and `\,` and `\"` are read as `,` and `"`, so `/\d{1\,2}/` compiles to `\d{1,2}`.
+    pub(crate) fn parse_regex(raw: &str) -> Option<(Cow<'_, str>, Result<Pattern, String>)> {
+        let rest = raw.strip_prefix('/')?;
+        let end = rest.rfind('/')?;
+        let (source, flags) = (&rest[..end], &rest[end + 1..]);
+        if !flags.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        if let Some(flag) = flags.chars().find(|c| !"imsxU".contains(*c)) {
+            return Some((
+                Cow::Borrowed(source),
+                Err(format!("unknown regex flag `{}`", flag)),
+            ));
+        }
+
+        if source.is_empty() {
+            return Some((Cow::Borrowed(source), Err("empty regex".to_string())));
+        }
+
+        let source = lexer::unescape_regex(source);
+        let source = if flags.is_empty() {
+            source
+        } else {
+            Cow::Owned(format!("(?{}){}", flags, source))
+        };
+        let pattern = Self::compile_regex(&source);
+        Some((source, pattern))
+    }
+
+    #[cfg(feature = "regex")]
+    fn compile_regex(source: &str) -> Result<Pattern, String> {
+        regex::Regex::new(source)
+            .map(Pattern::Regex)
+            .map_err(|error| error.to_string())
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn compile_regex(_source: &str) -> Result<Pattern, String> {
+        Err("regex keywords require the `regex` feature".to_string())
+    }
+
+    /// Returns a copy ready for [`find`](Self::find), with glob literals lowercased.
+    pub(crate) fn lowercase(&self) -> Pattern {
+        match self {
+            Pattern::Glob(glob) => Pattern::Glob(glob.lowercase()),
+            #[cfg(feature = "regex")]
+            Pattern::Regex(regex) => Pattern::Regex(regex.clone()),
+        }
+    }
+
+    /// Finds the pattern in `product`, returning a range in the lowercased product.
+    /// Globs are matched as they are, so they must be [lowercased](Self::lowercase) first.
+    /// In [`MatchMode::Word`] the match must start and end on a word boundary.
+    pub(crate) fn find(&self, product: &Haystack, mode: MatchMode) -> Option<Range<usize>> {
+        match self {
+            Pattern::Glob(glob) => {
+                let bounds = match mode {
+                    MatchMode::Substring => None,
+                    MatchMode::Word => Some(product.bounds()),
+                };
+                glob.find(&product.lower, bounds)
+            }
+            #[cfg(feature = "regex")]
+            Pattern::Regex(regex) => match mode {
+                MatchMode::Substring => regex
+                    .find(&product.text)
+                    .map(|m| product.to_lower(m.range())),
+                MatchMode::Word => {
+                    let bounds = product.text_bounds();
+                    let on_bound = |i: usize| bounds.binary_search(&i).is_ok();
+                    regex
+                        .find_iter(&product.text)
+                        .find(|m| on_bound(m.start()) && on_bound(m.end()))
+                        .map(|m| product.to_lower(m.range()))
+                }
+            },
+        }
+    }
+}
+
+/// How a [`Pattern`] is serialized, with regexes as their source.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Pattern")]
+enum PatternRepr {
+    Glob(Glob),
+    Regex(String),
+}
+
+#[cfg(feature = "serde")]
+impl From<Pattern> for PatternRepr {
+    fn from(pattern: Pattern) -> Self {
+        match pattern {
+            Pattern::Glob(glob) => PatternRepr::Glob(glob),
+            #[cfg(feature = "regex")]
+            Pattern::Regex(regex) => PatternRepr::Regex(regex.as_str().to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PatternRepr> for Pattern {
+    type Error = String;
+
+    fn try_from(repr: PatternRepr) -> Result<Self, Self::Error> {
+        match repr {
+            PatternRepr::Glob(glob) => Ok(Pattern::Glob(glob)),
+            PatternRepr::Regex(source) => Pattern::compile_regex(&source),
+        }
+    }
+}
+
+/// Regexes are compared by their source.
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Pattern::Glob(a), Pattern::Glob(b)) => a == b,
+            #[cfg(feature = "regex")]
+            (Pattern::Regex(a), Pattern::Regex(b)) => a.as_str() == b.as_str(),
+            #[cfg(feature = "regex")]
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{ErrorCode, Matchable, ParseError};
+use std::fmt;
+
+/// How a [`Predicate`] compares a product field with its value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Comparison {
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+    /// `=`
+    Equal,
+    /// `!=`
+    NotEqual,
+}
+
+impl Comparison {
+    /// Every operator, longer ones first so `<=` isn't read as `<`.
+    const OPERATORS: [(&'static str, Comparison); 6] = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("!=", Comparison::NotEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ];
+
+    /// The operator as it is written in keyword input.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+        }
+    }
+
+    /// Compares `left` with `right`.
+    pub fn compare(&self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+/// A numeric comparison against a product field, written without a prefix like
+/// `price<200`, `price>=90` or `stock>0`.
+///
+/// A product only satisfies the predicate when it has the field and the field
+/// holds a number, see [`Matchable::number`]. Plain strings have no numeric fields.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Predicate {
+    pub field: String,
+    pub comparison: Comparison,
+    pub value: f64,
+}
+
+impl Predicate {
+    /// Parses `field<op>value`, or returns `None` when `raw` isn't written that way.
+    /// Error spans are relative to `raw`.
+    pub(crate) fn parse(raw: &str) -> Option<Result<Self, ParseError>> {
+        let name = raw
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(raw.len());
+        let (field, rest) = raw.split_at(name);
+        if !field.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+        let (operator, comparison) = Comparison::OPERATORS
+            .iter()
+            .find(|(operator, _)| rest.starts_with(operator))?;
+
+        let start = name + operator.len();
+        let value = &raw[start..];
+        let parsed = value
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| {
+                let span = if value.is_empty() {
+                    name..start
+                } else {
+                    start..raw.len()
+                };
+                ParseError::new(
+                    span,
+                    ErrorCode::InvalidPredicate,
+                    format!("expected a number after `{}`", operator),
+                )
+            });
+        Some(parsed.map(|value| Self {
+            field: field.to_string(),
+            comparison: *comparison,
+            value,
+        }))
+    }
+
+    /// Checks whether the product's field satisfies the predicate.
+    /// ## Example
+    /// ```
+    /// use kwp::{Matchable, Parser, Prefixes};
+    /// use std::borrow::Cow;
+    ///
+    /// struct Product(&'static str);
+    ///
+    /// impl Matchable for Product {
+    ///     fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+    ///         match name {
+    ///             "price" => Some(Cow::Borrowed(self.0)),
+    ///             _ => None,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let keywords = Parser::new("price<200", Prefixes::default()).parse();
+    /// assert!(keywords.predicates[0].is_match(&Product("199.99")));
+    /// assert!(!keywords.predicates[0].is_match(&Product("200")));
+    /// ```
+    pub fn is_match<P: Matchable + ?Sized>(&self, product: &P) -> bool {
+        product
+            .number(&self.field)
+            .is_some_and(|number| self.comparison.compare(number, self.value))
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Writes the predicate as it is written in keyword input, e.g. `price<200`.
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.field, self.comparison, self.value)
+    }
+}
@@ -0,0 +1,220 @@
+//! Reads the `/products.json` payload of Shopify stores and matches its products
+//! variant by variant.
+//!
+//! Each variant is matched as a [`Listing`]: its product's fields together with
+//! the variant's own, so a result says which variants of a product matched.
+//! See [`Variants`] for the general model.
+//! ## Example
+//! ```
+//! use kwp::shopify::{self, Products};
+//! use kwp::{Parser, Prefixes};
+//!
+//! let json = r#"{ "products": [{
+//!     "id": 1,
+//!     "title": "Dunk Low",
+//!     "product_type": "Footwear",
+//!     "tags": ["retro"],
+//!     "variants": [
+//!         { "id": 10, "title": "Panda / 9", "price": "110.00" },
+//!         { "id": 11, "title": "Panda / 9 Wide", "price": "110.00" }
+//!     ]
+//! }] }"#;
+//! let products = Products::from_json(json).unwrap().products;
+//!
+//! let keywords = Parser::new("+dunk,-wide", Prefixes::default()).parse();
+//! let matches = shopify::match_products(&shopify::matcher(&keywords), &products);
+//!
+//! assert_eq!(matches[0].product.title, "Dunk Low");
+//! assert_eq!(matches[0].variants[0].id, 10);
+//! assert_eq!(matches[0].variants.len(), 1);
+//! ```
+use crate::{Keywords, Matchable, Matcher, VariantMatch, Variants};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+
+/// The product fields keywords are matched against by a [`matcher`].
+pub const FIELDS: [&str; 4] = ["title", "tags", "product_type", "variant"];
+
+/// Every field of a [`Listing`], for [`Parser::set_fields`](crate::Parser::set_fields)
+/// so that keywords may be scoped to any of them, e.g. `+vendor:nike`.
+pub const ALL_FIELDS: [&str; 9] = [
+    "title",
+    "handle",
+    "vendor",
+    "product_type",
+    "tags",
+    "variant",
+    "size",
+    "sku",
+    "price",
+];
+
+/// The title Shopify gives the only variant of a product without options.
+const DEFAULT_VARIANT: &str = "Default Title";
+
+/// The `/products.json` payload.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Products {
+    #[serde(default)]
+    pub products: Vec<Product>,
+}
+
+impl Products {
+    /// Deserializes the payload, ignoring the fields a [`Product`] doesn't have.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// A product of a Shopify store.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Product {
+    pub id: u64,
+    pub title: String,
+    pub handle: String,
+    pub vendor: String,
+    pub product_type: String,
+    /// Read from either a list or a comma separated string, the form the Admin API uses.
+    #[serde(deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    pub variants: Vec<Variant>,
+}
+
+/// A variant of a [`Product`], e.g. one size or colorway.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Variant {
+    pub id: u64,
+    /// The variant's option values joined with ` / `, e.g. `Grey / 9 / 2E Wide`.
+    pub title: String,
+    pub sku: Option<String>,
+    /// The price as Shopify writes it, e.g. `"180.00"`.
+    pub price: Option<String>,
+    pub available: bool,
+    pub option1: Option<String>,
+    pub option2: Option<String>,
+    pub option3: Option<String>,
+}
+
+impl Default for Variant {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            title: String::new(),
+            sku: None,
+            price: None,
+            available: true,
+            option1: None,
+            option2: None,
+            option3: None,
+        }
+    }
+}
+
+fn deserialize_tags<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        List(Vec<String>),
+        Joined(String),
+    }
+
+    Ok(match Option::<Tags>::deserialize(deserializer)? {
+        Some(Tags::List(tags)) => tags,
+        Some(Tags::Joined(tags)) => tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => vec![],
+    })
+}
+
+impl Product {
+    /// Returns the variants that match, each as a [`Listing`], in their original order.
+    /// ## Example
+    /// ```
+    /// use kwp::shopify::{self, Product, Variant};
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let product = Product {
+    ///     title: "990v6".to_string(),
+    ///     variants: vec![
+    ///         Variant { title: "9 / D".to_string(), ..Variant::default() },
+    ///         Variant { title: "9 / 2E Wide".to_string(), ..Variant::default() },
+    ///     ],
+    ///     ..Product::default()
+    /// };
+    /// let keywords = Parser::new("+990,-wide", Prefixes::default()).parse();
+    ///
+    /// let variants = product.matching_variants(&shopify::matcher(&keywords));
+    /// assert_eq!(variants, vec![&product.variants[0]]);
+    /// ```
+    pub fn matching_variants(&self, matcher: &Matcher) -> Vec<&Variant> {
+        matcher.match_variants(self)
+    }
+}
+
+/// Product fields: `title`, `handle`, `vendor`, `product_type` and `tags` joined with `, `.
+impl Matchable for Product {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "title" => Some(Cow::Borrowed(&self.title)),
+            "handle" => Some(Cow::Borrowed(&self.handle)),
+            "vendor" => Some(Cow::Borrowed(&self.vendor)),
+            "product_type" => Some(Cow::Borrowed(&self.product_type)),
+            "tags" => Some(Cow::Owned(self.tags.join(", "))),
+            _ => None,
+        }
+    }
+}
+
+impl Variants for Product {
+    type Variant = Variant;
+
+    fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+}
+
+impl Variant {
+    /// The title, unless it is the one Shopify gives variants of products without options.
+    fn option_title(&self) -> Option<&str> {
+        Some(self.title.as_str()).filter(|title| *title != DEFAULT_VARIANT)
+    }
+}
+
+/// Variant fields: `variant`, `sku`, `price` and `size`, where `size` is the variant
+/// title so that [size predicates](crate::SizeFilter) apply to it.
+impl Matchable for Variant {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "variant" | "size" => self.option_title().map(Cow::Borrowed),
+            "sku" => self.sku.as_deref().map(Cow::Borrowed),
+            "price" => self.price.as_deref().map(Cow::Borrowed),
+            _ => None,
+        }
+    }
+}
+
+/// A variant together with its product, matched as a single product with the
+/// fields of both.
+pub type Listing<'p> = crate::Listing<'p, Product>;
+
+/// A product that matched, with the variants that did.
+pub type ProductMatch<'p> = VariantMatch<'p, Product>;
+
+/// Compiles `keywords` into a [`Matcher`] that looks at the product [`FIELDS`].
+pub fn matcher(keywords: &Keywords) -> Matcher {
+    let mut matcher = keywords.compile();
+    matcher.set_fields(&FIELDS);
+    matcher
+}
+
+/// Finds the products with a matching variant, in their original order.
+/// Products without variants match on their own fields.
+pub fn match_products<'p>(matcher: &Matcher, products: &'p [Product]) -> Vec<ProductMatch<'p>> {
+    matcher.match_listings(products)
+}
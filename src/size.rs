@@ -0,0 +1,370 @@
+use crate::{ErrorCode, Matchable, ParseError};
+use std::fmt;
+
+/// A regional shoe size system.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SizeSystem {
+    UsMen,
+    UsWomen,
+    Uk,
+    Eu,
+    /// Foot length in centimetres, also used for Japanese sizes.
+    Cm,
+}
+
+impl SizeSystem {
+    /// Reads a unit like `us`, `m`, `w`, `uk`, `eu` or `cm`, lowercased.
+    fn from_unit(unit: &str) -> Option<Self> {
+        match unit {
+            "us" | "usm" | "m" | "men" | "mens" => Some(SizeSystem::UsMen),
+            "usw" | "w" | "wmns" | "women" | "womens" => Some(SizeSystem::UsWomen),
+            "uk" => Some(SizeSystem::Uk),
+            "eu" | "eur" => Some(SizeSystem::Eu),
+            "cm" | "jp" => Some(SizeSystem::Cm),
+            _ => None,
+        }
+    }
+
+    /// The unit written before a size, empty for US men's sizes.
+    fn unit(&self) -> &'static str {
+        match self {
+            SizeSystem::UsMen => "",
+            SizeSystem::UsWomen => "w",
+            SizeSystem::Uk => "uk",
+            SizeSystem::Eu => "eu",
+            SizeSystem::Cm => "cm",
+        }
+    }
+
+    /// The column of the system in [`CHART`], `None` for the ones derived from US men's sizes.
+    fn column(&self) -> Option<usize> {
+        match self {
+            SizeSystem::UsMen => Some(0),
+            SizeSystem::UsWomen => None,
+            SizeSystem::Uk => Some(1),
+            SizeSystem::Eu => Some(2),
+            SizeSystem::Cm => Some(3),
+        }
+    }
+}
+
+/// US men's sizes with their UK, EU and CM equivalents, every column ascending.
+const CHART: [[f64; 4]; 23] = [
+    [3.5, 3.0, 35.5, 22.5],
+    [4.0, 3.5, 36.0, 23.0],
+    [4.5, 4.0, 36.5, 23.5],
+    [5.0, 4.5, 37.5, 23.5],
+    [5.5, 5.0, 38.0, 24.0],
+    [6.0, 5.5, 38.5, 24.0],
+    [6.5, 6.0, 39.0, 24.5],
+    [7.0, 6.0, 40.0, 25.0],
+    [7.5, 6.5, 40.5, 25.5],
+    [8.0, 7.0, 41.0, 26.0],
+    [8.5, 7.5, 42.0, 26.5],
+    [9.0, 8.0, 42.5, 27.0],
+    [9.5, 8.5, 43.0, 27.5],
+    [10.0, 9.0, 44.0, 28.0],
+    [10.5, 9.5, 44.5, 28.5],
+    [11.0, 10.0, 45.0, 29.0],
+    [11.5, 10.5, 45.5, 29.5],
+    [12.0, 11.0, 46.0, 30.0],
+    [12.5, 11.5, 47.0, 30.5],
+    [13.0, 12.0, 47.5, 31.0],
+    [14.0, 13.0, 48.5, 32.0],
+    [15.0, 14.0, 49.5, 33.0],
+    [16.0, 15.0, 50.5, 34.0],
+];
+
+/// How much larger US women's sizes run than US men's ones.
+const WOMEN_OFFSET: f64 = 1.5;
+
+/// Sizes closer than this are the same size.
+const EPSILON: f64 = 1e-6;
+
+/// Maps `value` from one chart column to another, interpolating between rows.
+/// Values beyond the first or last row have no equivalent.
+fn interpolate(from: usize, to: usize, value: f64) -> Option<f64> {
+    if from == to {
+        return Some(value);
+    }
+    let (first, last) = (CHART[0], CHART[CHART.len() - 1]);
+    if value < first[from] - EPSILON || value > last[from] + EPSILON {
+        return None;
+    }
+    let i = CHART
+        .partition_point(|row| row[from] < value)
+        .clamp(1, CHART.len() - 1);
+    let (a, b) = (CHART[i - 1], CHART[i]);
+    if (b[from] - a[from]).abs() < EPSILON {
+        return Some(a[to]);
+    }
+    Some(a[to] + (value - a[from]) * (b[to] - a[to]) / (b[from] - a[from]))
+}
+
+/// The US men's sizes equivalent to `low..=high` in `system`, as an inclusive range.
+/// Every chart row in the range counts, so `uk6` covers both US 6.5 and US 7.
+/// Ranges beyond the ends of the chart have no equivalent.
+fn us_men(system: SizeSystem, low: f64, high: f64) -> Option<(f64, f64)> {
+    let column = match system.column() {
+        Some(0) => return Some((low, high)),
+        Some(column) => column,
+        None => return Some((low - WOMEN_OFFSET, high - WOMEN_OFFSET)),
+    };
+    let mut rows = CHART
+        .iter()
+        .filter(|row| row[column] >= low - EPSILON && row[column] <= high + EPSILON)
+        .map(|row| row[0]);
+    match rows.next() {
+        Some(first) => Some((first, rows.next_back().unwrap_or(first))),
+        None => Some((interpolate(column, 0, low)?, interpolate(column, 0, high)?)),
+    }
+}
+
+/// Splits a number with an optional unit before or after it, e.g. `uk8`, `10.5w` or `9`.
+fn split_unit(text: &str) -> Option<(Option<SizeSystem>, f64)> {
+    let start = text.find(|c: char| !c.is_ascii_alphabetic())?;
+    let end = text
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !c.is_ascii_alphabetic())
+        .map_or(text.len(), |(i, c)| i + c.len_utf8());
+    let (prefix, number, suffix) = (&text[..start], &text[start..end], &text[end..]);
+    let unit = match (prefix.is_empty(), suffix.is_empty()) {
+        (true, true) => None,
+        (false, true) => Some(SizeSystem::from_unit(prefix)?),
+        (true, false) => Some(SizeSystem::from_unit(suffix)?),
+        (false, false) => return None,
+    };
+    Some((unit, parse_number(number)?))
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    text.parse()
+        .ok()
+        .filter(|number: &f64| number.is_finite() && *number > 0.0)
+}
+
+/// A shoe size in one system, e.g. UK 8.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Size {
+    pub system: SizeSystem,
+    pub value: f64,
+}
+
+impl Size {
+    /// Finds the size in a variant title such as `US M 9 / W 10.5`, `EU 42.5`,
+    /// `27 cm` or `9.5`. The first size with a unit wins, and otherwise the first
+    /// number is read as a US men's size.
+    /// ## Example
+    /// ```
+    /// use kwp::{Size, SizeSystem};
+    ///
+    /// let size = Size::find("Air Max 90 / EU 42.5").unwrap();
+    /// assert_eq!(size, Size { system: SizeSystem::Eu, value: 42.5 });
+    ///
+    /// let size = Size::find("US W 10.5 / M 9").unwrap();
+    /// assert_eq!(size.system, SizeSystem::UsWomen);
+    /// ```
+    pub fn find(text: &str) -> Option<Size> {
+        let text = text.to_lowercase().replace("'s", "s");
+        let words: Vec<&str> = text
+            .split(|c: char| !(c.is_alphanumeric() || c == '.'))
+            .map(|word| word.trim_matches('.'))
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        let mut bare = None;
+        for (i, word) in words.iter().enumerate() {
+            let next = |n: usize| words.get(i + n).copied().unwrap_or_default();
+            if let Some(mut system) = SizeSystem::from_unit(word) {
+                let mut number = next(1);
+                if *word == "us" {
+                    if let Some(gender) = SizeSystem::from_unit(number) {
+                        system = gender;
+                        number = next(2);
+                    }
+                }
+                if let Some(value) = parse_number(number) {
+                    return Some(Size { system, value });
+                }
+            } else if let Some((unit, value)) = split_unit(word) {
+                // a unit followed by its own number starts the next size, as in `9 / W 10.5`
+                let after =
+                    SizeSystem::from_unit(next(1)).filter(|_| parse_number(next(2)).is_none());
+                match unit.or(after) {
+                    Some(system) => return Some(Size { system, value }),
+                    None => {
+                        bare.get_or_insert(Size {
+                            system: SizeSystem::UsMen,
+                            value,
+                        });
+                    }
+                }
+            }
+        }
+        bare
+    }
+
+    /// Converts the size to its closest equivalent in another system, or returns
+    /// `None` when it lies beyond the sizes the conversion chart covers.
+    /// ## Example
+    /// ```
+    /// use kwp::{Size, SizeSystem};
+    ///
+    /// let size = Size { system: SizeSystem::Uk, value: 8.0 };
+    /// assert_eq!(size.convert(SizeSystem::Eu).unwrap().value, 42.5);
+    /// assert_eq!(size.convert(SizeSystem::UsWomen).unwrap().value, 10.5);
+    ///
+    /// let tiny = Size { system: SizeSystem::Cm, value: 0.1 };
+    /// assert_eq!(tiny.convert(SizeSystem::UsMen), None);
+    /// ```
+    pub fn convert(&self, system: SizeSystem) -> Option<Size> {
+        let (us, _) = us_men(self.system, self.value, self.value)?;
+        let value = match system.column() {
+            Some(column) => interpolate(0, column, us)?,
+            None => us + WOMEN_OFFSET,
+        };
+        Some(Size { system, value })
+    }
+}
+
+/// An inclusive range of sizes in one system, e.g. `9-11.5` or `uk8`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SizeRange {
+    pub system: SizeSystem,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl SizeRange {
+    /// Parses `[unit]low[-high]`, where the unit defaults to US men's sizes and may
+    /// also follow the number, e.g. `10.5w`.
+    pub(crate) fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.to_ascii_lowercase();
+        let (low, high) = match spec.split_once('-') {
+            Some((low, high)) => (split_unit(low)?, Some(split_unit(high)?)),
+            None => (split_unit(&spec)?, None),
+        };
+        let system = match (low.0, high.and_then(|high| high.0)) {
+            (Some(a), Some(b)) if a != b => return None,
+            (unit, other) => unit.or(other).unwrap_or(SizeSystem::UsMen),
+        };
+        let high = high.map_or(low.1, |high| high.1);
+        if high < low.1 {
+            return None;
+        }
+        Some(Self {
+            system,
+            low: low.1,
+            high,
+        })
+    }
+
+    /// Checks whether `size` is within the range once both are converted to the same system.
+    /// Sizes and ranges beyond the conversion chart only contain sizes in their own system.
+    pub fn contains(&self, size: Size) -> bool {
+        let (low, high, size_low, size_high) = if self.system == size.system {
+            (self.low, self.high, size.value, size.value)
+        } else {
+            match (
+                us_men(self.system, self.low, self.high),
+                us_men(size.system, size.value, size.value),
+            ) {
+                (Some((low, high)), Some((size_low, size_high))) => {
+                    (low, high, size_low, size_high)
+                }
+                _ => return false,
+            }
+        };
+        low <= size_high + EPSILON && size_low <= high + EPSILON
+    }
+}
+
+/// A size predicate, written without a prefix like `size:9-11.5` or `size:uk8,eu42`.
+///
+/// Every size spec after `size:`, including the ones in the following unprefixed
+/// tokens, is a [`SizeRange`] a product may fall in. Products are checked through
+/// their `size` [field](Matchable::field), and only match when it holds a size.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SizeFilter {
+    pub ranges: Vec<SizeRange>,
+}
+
+impl SizeFilter {
+    /// Parses `size:spec`, or returns `None` when `raw` doesn't start with `size:`.
+    /// Error spans are relative to `raw`.
+    pub(crate) fn parse(raw: &str) -> Option<Result<Self, ParseError>> {
+        let prefix = raw.get(..5)?;
+        if !prefix.eq_ignore_ascii_case("size:") {
+            return None;
+        }
+        let spec = &raw[5..];
+        let range = SizeRange::parse(spec).ok_or_else(|| {
+            ParseError::new(
+                if spec.is_empty() { 0..5 } else { 5..raw.len() },
+                ErrorCode::InvalidPredicate,
+                "expected a size like `9`, `9-11.5` or `uk8`",
+            )
+        });
+        Some(range.map(|range| Self {
+            ranges: vec![range],
+        }))
+    }
+
+    /// Checks whether the first size found in `size` is in any of the ranges.
+    /// ## Example
+    /// ```
+    /// use kwp::{Parser, Prefixes};
+    ///
+    /// let keywords = Parser::new("+dunk,size:uk8,eu44", Prefixes::default()).parse();
+    /// let sizes = &keywords.sizes[0];
+    ///
+    /// assert!(sizes.matches_size("US 9"));
+    /// assert!(sizes.matches_size("M 10 / W 11.5"));
+    /// assert!(!sizes.matches_size("US 9.5"));
+    /// ```
+    pub fn matches_size(&self, size: &str) -> bool {
+        Size::find(size).is_some_and(|size| self.contains(size))
+    }
+
+    /// Checks whether `size` is in any of the ranges.
+    pub fn contains(&self, size: Size) -> bool {
+        self.ranges.iter().any(|range| range.contains(size))
+    }
+
+    /// Checks whether the product's `size` field matches.
+    pub fn is_match<P: Matchable + ?Sized>(&self, product: &P) -> bool {
+        product
+            .field("size")
+            .is_some_and(|size| self.matches_size(&size))
+    }
+}
+
+/// Writes the range as it is written after `size:`, e.g. `uk8-9`.
+impl fmt::Display for SizeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.system.unit(), self.low)?;
+        if self.high != self.low {
+            write!(f, "-{}", self.high)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the predicate as it is written in keyword input, e.g. `size:uk8,eu42`.
+impl fmt::Display for SizeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("size:")?;
+        for (i, range) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", range)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//! Filtering live product feeds delivered as a [`Stream`].
+
+use crate::{Matchable, Matcher};
+use futures_core::ready;
+use futures_core::stream::{FusedStream, Stream};
+use futures_core::task::{Context, Poll};
+use pin_project_lite::pin_project;
+use std::borrow::Borrow;
+use std::pin::Pin;
+
+pin_project! {
+    /// A stream of the products of another stream that a [`Matcher`] matches,
+    /// each yielded as soon as it arrives.
+    /// Created by [`FilterKeywordsStream::filter_keywords`].
+    #[derive(Debug, Clone)]
+    #[must_use = "streams do nothing unless polled"]
+    pub struct FilterStream<S, M> {
+        #[pin]
+        products: S,
+        matcher: M,
+    }
+}
+
+impl<S, M> FilterStream<S, M> {
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> S {
+        self.products
+    }
+}
+
+impl<S, M> Stream for FilterStream<S, M>
+where
+    S: Stream,
+    S::Item: Matchable,
+    M: Borrow<Matcher>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            match ready!(this.products.as_mut().poll_next(cx)) {
+                Some(product) if (*this.matcher).borrow().is_match(&product) => {
+                    return Poll::Ready(Some(product))
+                }
+                Some(_) => {}
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.products.size_hint().1)
+    }
+}
+
+impl<S, M> FusedStream for FilterStream<S, M>
+where
+    S: FusedStream,
+    S::Item: Matchable,
+    M: Borrow<Matcher>,
+{
+    fn is_terminated(&self) -> bool {
+        self.products.is_terminated()
+    }
+}
+
+/// Adds [`filter_keywords`](Self::filter_keywords) to every [`Stream`] of [`Matchable`] products.
+pub trait FilterKeywordsStream: Stream + Sized {
+    /// Keeps the products `matcher` matches. The matcher may be borrowed, owned or
+    /// shared, e.g. an `Arc<Matcher>` for a stream that is moved into a spawned task.
+    /// ⚠️ Case insensitive
+    /// ## Example
+    /// ```
+    /// use futures::executor::block_on;
+    /// use futures::stream::{self, StreamExt};
+    /// use kwp::{FilterKeywordsStream, Parser, Prefixes};
+    /// use std::sync::Arc;
+    ///
+    /// let matcher = Parser::new("+jordan,-youth", Prefixes::default()).parse().compile();
+    /// let feed = stream::iter(vec!["Jordan 1 Youth", "Jordan 1 High", "Dunk Low"]);
+    ///
+    /// let mut matches = feed.filter_keywords(Arc::new(matcher));
+    /// assert_eq!(block_on(matches.next()), Some("Jordan 1 High"));
+    /// assert_eq!(block_on(matches.next()), None);
+    /// ```
+    fn filter_keywords<M: Borrow<Matcher>>(self, matcher: M) -> FilterStream<Self, M>
+    where
+        Self::Item: Matchable,
+    {
+        FilterStream {
+            products: self,
+            matcher,
+        }
+    }
+}
+
+impl<S: Stream> FilterKeywordsStream for S {}
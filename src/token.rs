@@ -0,0 +1,158 @@
+use crate::keyword::Defaults;
+use crate::{lexer, KeywordRef, Predicate, Prefixes, SizeFilter, SizeRange};
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+
+/// Which prefix, if any, a token starts with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Polarity {
+    Positive,
+    Negative,
+    /// The token starts with neither prefix.
+    Other,
+}
+
+/// A single comma separated keyword of the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token<'a> {
+    pub polarity: Polarity,
+    /// The keyword text, or the whole unescaped token when it has no prefix.
+    pub text: Cow<'a, str>,
+    /// The token exactly as it was written.
+    pub raw: &'a str,
+    /// Byte range of `raw` in the input.
+    pub span: Range<usize>,
+    /// What the token was parsed as.
+    pub kind: TokenKind<'a>,
+}
+
+/// What a [`Token`] was parsed as, and so where it ends up in [`Keywords`](crate::Keywords).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind<'a> {
+    /// A positive or negative keyword.
+    Keyword(KeywordRef<'a>),
+    /// An unprefixed comparison like `price<200`.
+    Predicate(Predicate),
+    /// An unprefixed size predicate like `size:uk8`.
+    Size(SizeFilter),
+    /// A size range extending the size predicate before it, like `eu42` in `size:uk8,eu42`.
+    SizeRange(SizeRange),
+    /// Anything else, kept in [`Keywords::other`](crate::Keywords::other).
+    Other,
+}
+
+/// Writes the token exactly as it was written.
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.raw)
+    }
+}
+
+/// An iterator over the raw comma separated tokens of an input and their start offsets.
+/// An empty input has none.
+#[derive(Debug, Clone)]
+pub(crate) struct Split<'a> {
+    input: &'a str,
+    next: Option<usize>,
+}
+
+impl<'a> Split<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            next: Some(0).filter(|_| !input.is_empty()),
+        }
+    }
+}
+
+impl<'a> Iterator for Split<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.next?;
+        let rest = &self.input[start..];
+        let len = lexer::scan(rest, &[',']);
+        self.next = if len < rest.len() {
+            Some(start + len + 1)
+        } else {
+            None
+        };
+        Some((start, &rest[..len]))
+    }
+}
+
+/// An iterator over the [`Token`]s of an input, in input order.
+/// Created by [`Parser::tokens`](crate::Parser::tokens).
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    split: Split<'a>,
+    prefixes: Prefixes<'a>,
+    defaults: Defaults<'a>,
+    retain_prefix: bool,
+    /// Whether the last token was a size predicate, which later size ranges extend.
+    sizing: bool,
+}
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(
+        input: &'a str,
+        prefixes: Prefixes<'a>,
+        defaults: Defaults<'a>,
+        retain_prefix: bool,
+    ) -> Self {
+        Self {
+            split: Split::new(input),
+            prefixes,
+            defaults,
+            retain_prefix,
+            sizing: false,
+        }
+    }
+
+    /// Classifies a raw token the way [`Parser::parse`](crate::Parser::parse) does.
+    fn classify(&mut self, raw: &'a str) -> (Polarity, Cow<'a, str>, TokenKind<'a>) {
+        let extends = self.sizing;
+        self.sizing = false;
+        let (polarity, prefix) = match self.prefixes.strip(raw) {
+            Some(found) => found,
+            None => {
+                let trimmed = raw.trim();
+                let kind = if let (true, Some(range)) = (extends, SizeRange::parse(trimmed)) {
+                    self.sizing = true;
+                    TokenKind::SizeRange(range)
+                } else if let Some(Ok(sizes)) = SizeFilter::parse(trimmed) {
+                    self.sizing = true;
+                    TokenKind::Size(sizes)
+                } else if let Some(Ok(predicate)) = Predicate::parse(trimmed) {
+                    TokenKind::Predicate(predicate)
+                } else {
+                    TokenKind::Other
+                };
+                return (Polarity::Other, lexer::unescape(raw), kind);
+            }
+        };
+        let mut keyword = KeywordRef::parse_lenient(&raw[prefix.len()..], self.defaults, polarity);
+        if self.retain_prefix {
+            keyword.text = Cow::Owned(format!("{}{}", prefix, keyword.text));
+        }
+        (polarity, keyword.text.clone(), TokenKind::Keyword(keyword))
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, raw) = self.split.next()?;
+        let (polarity, text, kind) = self.classify(raw);
+        Some(Token {
+            polarity,
+            text,
+            raw,
+            span: start..start + raw.len(),
+            kind,
+        })
+    }
+}
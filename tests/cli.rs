@@ -0,0 +1,257 @@
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+const TITLES: &str = "Jordan 1 Youth\nJordan 1 High\nDunk Low\nJordan 4 Retro\n";
+
+/// Runs `kwp` with `args`, writing `stdin` to it from another thread so that
+/// neither side blocks. `kwp` may exit without reading it, e.g. on invalid keywords.
+fn kwp(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_kwp"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut input = child.stdin.take().unwrap();
+    let stdin = stdin.to_string();
+    let writer = thread::spawn(move || match input.write_all(stdin.as_bytes()) {
+        Err(error) if error.kind() != ErrorKind::BrokenPipe => Err(error),
+        _ => Ok(()),
+    });
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap().unwrap();
+    output
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn filters_lines() {
+    let output = kwp(&["+jordan,-youth"], TITLES);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "Jordan 1 High\nJordan 4 Retro\n");
+
+    let output = kwp(&["-v", "+jordan,-youth"], TITLES);
+    assert_eq!(stdout(&output), "Jordan 1 Youth\nDunk Low\n");
+
+    let output = kwp(&["-c", "+jordan"], TITLES);
+    assert_eq!(stdout(&output), "3\n");
+
+    // keywords may start with a negative keyword instead of a flag
+    let output = kwp(&["-youth,+jordan"], TITLES);
+    assert_eq!(stdout(&output), "Jordan 1 High\nJordan 4 Retro\n");
+
+    let output = kwp(&["-c", "-youth,+jordan"], TITLES);
+    assert_eq!(stdout(&output), "2\n");
+
+    let output = kwp(&["-w", "+dun"], TITLES);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+
+    // a trailing carriage return is not part of the line
+    let output = kwp(&["+low"], "Dunk Low\r\n");
+    assert_eq!(stdout(&output), "Dunk Low\n");
+}
+
+#[test]
+fn exit_codes() {
+    assert_eq!(kwp(&["+dunk"], TITLES).status.code(), Some(0));
+    assert_eq!(kwp(&["+yeezy"], TITLES).status.code(), Some(1));
+    assert_eq!(kwp(&["+yeezy"], "").status.code(), Some(1));
+    assert_eq!(kwp(&["-c", "+yeezy"], TITLES).status.code(), Some(1));
+
+    let output = kwp(&["+jordan,youth"], TITLES);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("missing-prefix"));
+    assert!(stderr.contains("         ^^^^^"));
+
+    let output = kwp(&["+jordan", "does/not/exist.txt"], TITLES);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn files() {
+    let dir = std::env::temp_dir().join(format!("kwp-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("titles.txt");
+    std::fs::write(&path, TITLES).unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = kwp(&["+retro", path], "");
+    assert_eq!(stdout(&output), "Jordan 4 Retro\n");
+
+    let output = kwp(&["+retro", path, "-"], "Retro Tee\n");
+    assert_eq!(
+        stdout(&output),
+        format!("{}:Jordan 4 Retro\n-:Retro Tee\n", path)
+    );
+
+    let output = kwp(&["-c", "+jordan", path, "-"], "Jordan 11\n");
+    assert_eq!(stdout(&output), format!("{}:3\n-:1\n", path));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn highlighting() {
+    let output = kwp(&["--color", "always", "+jordan,+high,+dan 1"], TITLES);
+    assert_eq!(
+        stdout(&output),
+        "\x1b[1;31mJordan 1\x1b[0m Youth\n\
+         \x1b[1;31mJordan 1\x1b[0m \x1b[1;31mHigh\x1b[0m\n\
+         \x1b[1;31mJordan\x1b[0m 4 Retro\n"
+    );
+
+    // output that isn't a terminal is never highlighted by default
+    let output = kwp(&["+jordan"], TITLES);
+    assert!(!stdout(&output).contains('\x1b'));
+
+    let output = kwp(&["--color", "always", "-v", "+jordan"], TITLES);
+    assert_eq!(stdout(&output), "Dunk Low\n");
+}
+
+const JSONL: &str = r#"{"title":"Jordan 1 High","vendor":"Nike","tags":["retro","hi"],"price":180}
+{"title":"Jordan 1 Youth","vendor":"Nike","tags":["kids"],"price":90}
+
+{"title":"Dunk Low","vendor":"Nike","tags":["retro"],"price":110}
+"#;
+
+const CSV: &str =
+    "title,vendor,price\nJordan 1 High,Nike,180\n\"Dunk Low, Panda\",Nike,110\nSamba,Adidas,100\n";
+
+#[test]
+fn json_lines() {
+    let output = kwp(&["--input", "jsonl", "+jordan,-youth"], JSONL);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        JSONL.lines().next().unwrap().to_owned() + "\n"
+    );
+
+    let args = ["--input", "jsonl", "--field", "title", "--field", "tags"];
+    let output = kwp(&[&args[..], &["+retro,price<150"]].concat(), JSONL);
+    assert_eq!(
+        stdout(&output),
+        JSONL.lines().nth(3).unwrap().to_owned() + "\n"
+    );
+
+    let output = kwp(&["--input", "jsonl", "price<150"], JSONL);
+    assert_eq!(
+        stdout(&output),
+        [1, 3]
+            .map(|i| JSONL.lines().nth(i).unwrap().to_owned() + "\n")
+            .concat()
+    );
+
+    let output = kwp(&["--input", "jsonl", "-c", "+nike"], JSONL);
+    assert_eq!(stdout(&output), "0\n");
+    let output = kwp(&["--input", "jsonl", "-c", "+vendor:nike"], JSONL);
+    assert_eq!(stdout(&output), "3\n");
+
+    // records that aren't JSON objects are reported, and the rest still filtered
+    let output = kwp(
+        &["--input", "jsonl", "+dunk"],
+        "[1]\n{\"title\":\"Dunk\"}\n",
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stdout(&output), "{\"title\":\"Dunk\"}\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 1: expected a JSON object"));
+
+    // lines are written back as they were read, with their line endings
+    let input = "{\"title\":\"Dunk Low\"}\r\n{\"title\":\"Air Max\"}\r\n{\"title\":\"Dunk\"}";
+    let output = kwp(&["--input", "jsonl", "+dunk"], input);
+    assert_eq!(
+        stdout(&output),
+        "{\"title\":\"Dunk Low\"}\r\n{\"title\":\"Dunk\"}\n"
+    );
+    let output = kwp(&["--input", "jsonl", "--output", "json", "+low"], input);
+    assert_eq!(stdout(&output), "{\"title\":\"Dunk Low\"}\n");
+
+    // lines that aren't UTF-8 are reported instead of being altered
+    let dir = std::env::temp_dir().join(format!("kwp-jsonl-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("products.jsonl");
+    std::fs::write(&path, b"{\"title\":\"Dunk \xff\"}\n{\"title\":\"Dunk\"}\n").unwrap();
+    let output = kwp(&["--input", "jsonl", "+dunk", path.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stdout(&output), "{\"title\":\"Dunk\"}\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 1: invalid utf-8"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn csv_records() {
+    let output = kwp(&["--input", "csv", "+low"], CSV);
+    assert_eq!(
+        stdout(&output),
+        "title,vendor,price\n\"Dunk Low, Panda\",Nike,110\n"
+    );
+
+    let output = kwp(&["--input", "csv", "--field", "vendor", "+nike"], CSV);
+    assert_eq!(
+        stdout(&output),
+        "title,vendor,price\nJordan 1 High,Nike,180\n\"Dunk Low, Panda\",Nike,110\n"
+    );
+
+    let output = kwp(&["--input", "csv", "+samba,price<=100"], CSV);
+    assert_eq!(stdout(&output), "title,vendor,price\nSamba,Adidas,100\n");
+
+    let output = kwp(&["--input", "csv", "-v", "+samba"], "title\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+
+    let output = kwp(&["--field", "vendor", "+nike"], CSV);
+    assert_eq!(output.status.code(), Some(2));
+
+    // records are written back byte for byte
+    let input = "\"title\",vendor\r\n\"Samba\",Adidas\r\nDunk,Nike\r\n\"Gazelle\nIndoor\",Adidas";
+    let output = kwp(&["--input", "csv", "--field", "vendor", "+adidas"], input);
+    assert_eq!(
+        stdout(&output),
+        "\"title\",vendor\r\n\"Samba\",Adidas\r\n\"Gazelle\nIndoor\",Adidas\n"
+    );
+}
+
+#[test]
+fn output_formats() {
+    let output = kwp(&["--input", "csv", "--output", "json", "+low"], CSV);
+    assert_eq!(
+        stdout(&output),
+        "{\"price\":\"110\",\"title\":\"Dunk Low, Panda\",\"vendor\":\"Nike\"}\n"
+    );
+    let output = kwp(&["--input", "jsonl", "--output", "json", "+dunk"], JSONL);
+    assert_eq!(
+        stdout(&output),
+        JSONL.lines().nth(3).unwrap().to_owned() + "\n"
+    );
+    let output = kwp(&["--output", "json", "+dunk"], TITLES);
+    assert_eq!(stdout(&output), "{\"title\":\"Dunk Low\"}\n");
+
+    let output = kwp(&["--input", "jsonl", "--output", "csv", "+jordan"], JSONL);
+    assert_eq!(
+        stdout(&output),
+        "price,tags,title,vendor\n\
+         180,\"retro, hi\",Jordan 1 High,Nike\n\
+         90,kids,Jordan 1 Youth,Nike\n"
+    );
+
+    let output = kwp(
+        &["--input", "csv", "--output", "table", "-v", "+samba"],
+        CSV,
+    );
+    assert_eq!(
+        stdout(&output),
+        "title            vendor  price\n\
+         Jordan 1 High    Nike    180\n\
+         Dunk Low, Panda  Nike    110\n"
+    );
+    let output = kwp(&["--output", "table", "+yeezy"], TITLES);
+    assert_eq!(stdout(&output), "");
+}
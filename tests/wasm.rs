@@ -1,4 +1,8 @@
-use kwp::{Parser, Prefixes};
+use kwp::{
+    Comparison, ErrorCode, Expr, FilterKeywords, Fuzziness, Glob, Keywords, MatchMode, Matchable,
+    Parser, Pattern, Polarity, Prefixes, Size, SizeSystem, TokenKind, WithVariants,
+};
+use std::borrow::Cow;
 use wasm_bindgen_test::*;
 
 #[wasm_bindgen_test]
@@ -45,6 +49,29 @@ fn weird_prefixes() {
     assert_eq!(keywords.negative, vec!["bar"]);
 }
 
+#[wasm_bindgen_test]
+fn empty_prefixes() {
+    let empty = Prefixes {
+        positive: "",
+        negative: "",
+    };
+    let keywords = Parser::new("foo,-bar", empty).parse();
+    assert!(keywords.positive.is_empty());
+    assert!(keywords.negative.is_empty());
+    assert_eq!(keywords.other, vec!["foo", "-bar"]);
+
+    let expr = Parser::new("foo|bar", empty).parse_expr().unwrap();
+    assert!(expr.matches("Foo Tee"));
+
+    let negative_only = Prefixes {
+        positive: "",
+        negative: "-",
+    };
+    let keywords = Parser::new("-bar,foo", negative_only).parse();
+    assert_eq!(keywords.negative, vec!["bar"]);
+    assert_eq!(keywords.other, vec!["foo"]);
+}
+
 #[wasm_bindgen_test]
 fn unparsed() {
     let parser = Parser::new(
@@ -72,3 +99,1074 @@ fn basic_products() {
     let products = parser.match_products(products.clone(), keywords.clone());
     assert_eq!(products, vec!["MyProduct Adult"]);
 }
+
+#[wasm_bindgen_test]
+fn expression_groups() {
+    let parser = Parser::new("(+jordan|+dunk),+low,-(youth|gs)", Prefixes::default());
+    let expr = parser.parse_expr().unwrap();
+    assert_eq!(
+        expr,
+        Expr::And(vec![
+            Expr::Or(vec![Expr::Term("jordan".into()), Expr::Term("dunk".into())]),
+            Expr::Term("low".into()),
+            Expr::Not(Box::new(Expr::Or(vec![
+                Expr::Term("youth".into()),
+                Expr::Term("gs".into())
+            ]))),
+        ])
+    );
+
+    let products = vec![
+        "Air Jordan 1 Low",
+        "Nike Dunk Low (GS)",
+        "Nike Dunk High",
+        "Nike SB Dunk Low Pro",
+    ];
+    let matched: Vec<_> = products.into_iter().filter(|p| expr.matches(p)).collect();
+    assert_eq!(matched, vec!["Air Jordan 1 Low", "Nike SB Dunk Low Pro"]);
+}
+
+#[wasm_bindgen_test]
+fn expression_errors() {
+    let unclosed = Parser::new("(+foo|+bar", Prefixes::default()).parse_expr();
+    let errors = unclosed.unwrap_err();
+    assert_eq!(errors[0].code, ErrorCode::UnclosedGroup);
+    assert_eq!(errors[0].span, 0..1);
+
+    let unmatched = Parser::new("+foo)", Prefixes::default()).parse_expr();
+    let errors = unmatched.unwrap_err();
+    assert_eq!(errors[0].code, ErrorCode::UnmatchedParen);
+    assert_eq!(errors[0].span, 4..5);
+
+    let empty = Parser::new("+foo,,+bar", Prefixes::default()).parse_expr();
+    assert_eq!(empty.unwrap_err()[0].code, ErrorCode::EmptyKeyword);
+
+    let dangling = Parser::new("+foo|-", Prefixes::default()).parse_expr();
+    assert_eq!(dangling.unwrap_err()[0].span, 5..6);
+}
+
+#[wasm_bindgen_test]
+fn quoted_and_escaped() {
+    let parser = Parser::new(
+        r#"+c++,+"air max 1, 86",-"say \"hi\"",+foo\,bar,\+baz"#,
+        Prefixes::default(),
+    );
+    let keywords = parser.parse();
+    assert_eq!(keywords.positive, vec!["c++", "air max 1, 86", "foo,bar"]);
+    assert_eq!(keywords.negative, vec![r#"say "hi""#]);
+    assert_eq!(keywords.other, vec!["+baz"]);
+
+    let products = vec!["Air Max 1, 86 OG", "Air Max 90"];
+    let products = parser.match_products(products, keywords);
+    assert_eq!(products, vec!["Air Max 1, 86 OG"]);
+}
+
+#[wasm_bindgen_test]
+fn quoted_expression() {
+    let parser = Parser::new(r#"+"(gs)"|+"1|2",-\(ps\)"#, Prefixes::default());
+    let expr = parser.parse_expr().unwrap();
+    assert!(expr.matches("Dunk Low (GS)"));
+    assert!(expr.matches("Jordan 1|2"));
+    assert!(!expr.matches("Dunk Low (GS) (PS)"));
+}
+
+#[wasm_bindgen_test]
+fn strict_parsing() {
+    let parser = Parser::new("+foo,-bar", Prefixes::default());
+    let keywords = parser.try_parse().unwrap();
+    assert_eq!(keywords.positive, vec!["foo"]);
+    assert_eq!(keywords.negative, vec!["bar"]);
+
+    let keywords = Parser::new("", Prefixes::default()).try_parse().unwrap();
+    assert_eq!(keywords, Keywords::default());
+
+    let parser = Parser::new(r#"+foo,,bar, -baz,+"qux"#, Prefixes::default());
+    let errors = parser.try_parse().unwrap_err();
+    let found: Vec<_> = errors.iter().map(|e| (e.code, e.span.clone())).collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorCode::EmptyKeyword, 5..5),
+            (ErrorCode::MissingPrefix, 6..9),
+            (ErrorCode::StrayWhitespace, 10..11),
+            (ErrorCode::UnterminatedQuote, 17..21),
+        ]
+    );
+
+    // the lenient parser still accepts everything
+    let keywords = parser.parse();
+    assert_eq!(keywords.positive, vec!["foo", "qux"]);
+    assert_eq!(keywords.other, vec!["", "bar", " -baz"]);
+}
+
+#[wasm_bindgen_test]
+fn ordered_tokens() {
+    let parser = Parser::new(r#"bar,+foobar,-"a,b",baz"#, Prefixes::default());
+    let tokens: Vec<_> = parser
+        .tokens()
+        .map(|t| (t.polarity, t.text.into_owned(), t.raw, t.span))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            (Polarity::Other, "bar".to_string(), "bar", 0..3),
+            (Polarity::Positive, "foobar".to_string(), "+foobar", 4..11),
+            (Polarity::Negative, "a,b".to_string(), r#"-"a,b""#, 12..18),
+            (Polarity::Other, "baz".to_string(), "baz", 19..22),
+        ]
+    );
+
+    // `bar` is only a substring of `foobar`, so it must stay in `other`
+    let keywords = parser.parse();
+    assert_eq!(keywords.other, vec!["bar", "baz"]);
+
+    // the tokens classify everything the way `parse` does
+    let mut parser = Parser::new(
+        "+foo,-bar,price<200,size:uk8,eu42,baz,+=max^2",
+        Prefixes::default(),
+    );
+    parser.should_retain_prefix(true);
+    let kinds: Vec<_> = parser.tokens().map(|t| t.kind).collect();
+    assert!(matches!(kinds[2], TokenKind::Predicate(_)));
+    assert!(matches!(kinds[3], TokenKind::Size(_)));
+    assert!(matches!(kinds[4], TokenKind::SizeRange(_)));
+    assert_eq!(kinds[5], TokenKind::Other);
+
+    let keywords = parser.parse();
+    let keyword_texts: Vec<_> = parser
+        .tokens()
+        .filter(|t| matches!(t.kind, TokenKind::Keyword(_)))
+        .map(|t| t.text.into_owned())
+        .collect();
+    assert_eq!(keyword_texts, vec!["+foo", "-bar", "+max"]);
+    assert_eq!(keywords.positive, vec!["+foo", "+max"]);
+    assert_eq!(keywords.negative, vec!["-bar"]);
+    assert_eq!(keywords.sizes[0].ranges.len(), 2);
+    assert_eq!(keywords.predicates.len(), 1);
+    assert_eq!(keywords.other, vec!["baz"]);
+    assert_eq!(Parser::new("", Prefixes::default()).tokens().count(), 0);
+}
+
+#[wasm_bindgen_test]
+fn word_boundaries() {
+    let products = vec![
+        "Kidney Bean Tee",
+        "Air Max 90 (Kids)",
+        "Maximum Hoodie",
+        "Nike Air Max-1",
+    ];
+
+    let parser = Parser::new("+=max,-=kid", Prefixes::default());
+    let keywords = parser.parse();
+    assert_eq!(keywords.positive[0].mode, MatchMode::Word);
+    let matched = parser.match_products(products.clone(), keywords);
+    assert_eq!(matched, vec!["Air Max 90 (Kids)", "Nike Air Max-1"]);
+
+    let mut parser = Parser::new("+tee,+max,-kid", Prefixes::default());
+    parser.set_match_mode(MatchMode::Word);
+    let keywords = parser.parse();
+    let matched = parser.match_products(products.clone(), keywords);
+    assert_eq!(
+        matched,
+        vec!["Kidney Bean Tee", "Air Max 90 (Kids)", "Nike Air Max-1"]
+    );
+
+    // substring matching stays the default
+    let parser = Parser::new("+max,-kid", Prefixes::default());
+    let keywords = parser.parse();
+    let matched = parser.match_products(products, keywords);
+    assert_eq!(matched, vec!["Maximum Hoodie", "Nike Air Max-1"]);
+}
+
+#[wasm_bindgen_test]
+fn fuzzy_keywords() {
+    let products = vec![
+        "Jordon 1 Retro High",
+        "Jordan 1 Retro Hihg",
+        "Dunk Low Yuoth",
+    ];
+
+    let parser = Parser::new(r#"+jordan~,-"high"~1,+dunk,+tilde\~"#, Prefixes::default());
+    let keywords = parser.parse();
+    assert_eq!(keywords.positive[0].fuzziness, Fuzziness::AUTO);
+    assert_eq!(keywords.negative[0].fuzziness, Fuzziness::Fixed(1));
+    assert_eq!(keywords.positive[2], "tilde~");
+
+    let matched = parser.match_products(products.clone(), keywords);
+    assert_eq!(matched, vec!["Dunk Low Yuoth"]);
+
+    let mut parser = Parser::new("+=jordan,-youth", Prefixes::default());
+    parser.set_fuzziness(Polarity::Negative, Fuzziness::Fixed(1));
+    let keywords = parser.parse();
+    assert_eq!(keywords.positive[0].fuzziness, Fuzziness::Exact);
+    let matched = parser.match_products_with_distance(products.clone(), keywords);
+    assert_eq!(matched, vec![("Jordan 1 Retro Hihg".to_string(), 0)]);
+
+    parser.set_fuzziness(Polarity::Positive, Fuzziness::Fixed(1));
+    let keywords = parser.parse();
+    let matched = parser.match_products_with_distance(products, keywords);
+    assert_eq!(
+        matched,
+        vec![
+            ("Jordon 1 Retro High".to_string(), 1),
+            ("Jordan 1 Retro Hihg".to_string(), 0)
+        ]
+    );
+}
+
+#[wasm_bindgen_test]
+fn glob_keywords() {
+    let products = vec![
+        "Nike Air Max 1 Premium",
+        "Nike Air Force 1 Low",
+        "Nike Air Max 90",
+        "Sizes 9 to 11",
+        "Wild Card Tee *",
+    ];
+
+    let parser = Parser::new(r#"+air*1,+"card tee *",-size?"#, Prefixes::default());
+    let keywords = parser.parse();
+    assert_eq!(
+        keywords.positive[0].pattern,
+        Some(Pattern::Glob(Glob::new("air*1")))
+    );
+    assert_eq!(keywords.positive[0], "air*1");
+    assert_eq!(keywords.positive[1].pattern, None);
+
+    let matched = parser.match_products(products.clone(), keywords);
+    assert_eq!(
+        matched,
+        vec![
+            "Nike Air Max 1 Premium",
+            "Nike Air Force 1 Low",
+            "Wild Card Tee *"
+        ]
+    );
+
+    let parser = Parser::new("+=a?r,+=max*", Prefixes::default());
+    let keywords = parser.parse();
+    let matched = parser.match_products(products, keywords);
+    assert_eq!(
+        matched,
+        vec![
+            "Nike Air Max 1 Premium",
+            "Nike Air Force 1 Low",
+            "Nike Air Max 90"
+        ]
+    );
+}
+
+#[wasm_bindgen_test]
+fn invalid_regex_keywords() {
+    let parser = Parser::new("+foo,+/dd(1391/,-/bar/q", Prefixes::default());
+    let errors = parser.try_parse().unwrap_err();
+    let found: Vec<_> = errors.iter().map(|e| (e.code, e.span.clone())).collect();
+    if cfg!(feature = "regex") {
+        assert_eq!(
+            found,
+            vec![
+                (ErrorCode::InvalidRegex, 6..15),
+                (ErrorCode::InvalidRegex, 17..23),
+            ]
+        );
+    } else {
+        assert_eq!(found[0], (ErrorCode::InvalidRegex, 6..15));
+    }
+
+    // the lenient parser keeps invalid patterns as plain text
+    let keywords = parser.parse();
+    assert_eq!(keywords.positive[1], "/dd(1391/");
+    assert_eq!(keywords.positive[1].pattern, None);
+
+    // an empty regex would match every product
+    let parser = Parser::new("+//i,-//", Prefixes::default());
+    let errors = parser.try_parse().unwrap_err();
+    let found: Vec<_> = errors.iter().map(|e| (e.code, e.span.clone())).collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorCode::InvalidRegex, 1..4),
+            (ErrorCode::InvalidRegex, 6..8)
+        ]
+    );
+    assert!(parser
+        .match_products(vec!["Dunk Low"], parser.parse())
+        .is_empty());
+}
+
+#[wasm_bindgen_test]
+fn compiled_matcher() {
+    let products = vec![
+        "Nike Dunk Low Retro",
+        "Nike Dunk Low (GS)",
+        "Air Jordan 1 Mid",
+        "Air Jordon 1 Low",
+        "Kidney Bean Tee",
+        "Wumpus Hat",
+    ];
+    let parser = Parser::new("+dunk,+jordan~,+=tee,+h?t,-(gs),-=mid", Prefixes::default());
+    let keywords = parser.parse();
+    let matcher = keywords.compile();
+
+    let matched = matcher.match_products(&products);
+    assert_eq!(
+        matched,
+        vec![
+            "Nike Dunk Low Retro",
+            "Air Jordon 1 Low",
+            "Kidney Bean Tee",
+            "Wumpus Hat"
+        ]
+    );
+    assert_eq!(matcher.distance("Air Jordon 1 Low"), Some(1));
+    assert_eq!(matcher.distance("Air Jordan 1 Mid"), None);
+
+    // the compiled matcher agrees with `match_products`
+    assert_eq!(parser.match_products(products, keywords), matched);
+}
+
+#[wasm_bindgen_test]
+fn relevance_scoring() {
+    let products = vec![
+        "Hoodie with Jordan Logo",
+        "Jordan Essentials Hoodie",
+        "Jordan Hoodie",
+        "Jordanstown Hoodie",
+        "Jordan Tee (Youth)",
+    ];
+    let parser = Parser::new("+jordan,+hoodie^0.5,-youth", Prefixes::default());
+    let keywords = parser.parse();
+    assert_eq!(keywords.positive[1].weight, 0.5);
+    assert_eq!(keywords.positive[1], "hoodie");
+
+    let matcher = keywords.compile();
+    let ranked: Vec<_> = matcher
+        .rank(&products)
+        .into_iter()
+        .map(|(p, _)| p)
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![
+            "Jordan Hoodie",
+            "Jordan Essentials Hoodie",
+            "Hoodie with Jordan Logo",
+            "Jordanstown Hoodie",
+        ]
+    );
+    assert_eq!(matcher.best(&products), Some("Jordan Hoodie"));
+    assert_eq!(matcher.score("Jordan Tee (Youth)"), None);
+
+    // modifiers can be combined in any order
+    let parser = Parser::new("+=jordan~^3,+hoodie^2~1", Prefixes::default());
+    let keywords = parser.parse();
+    assert_eq!(keywords.positive[0].weight, 3.0);
+    assert_eq!(keywords.positive[0].fuzziness, Fuzziness::AUTO);
+    assert_eq!(keywords.positive[1].weight, 2.0);
+    assert_eq!(keywords.positive[1].fuzziness, Fuzziness::Fixed(1));
+}
+
+#[wasm_bindgen_test]
+fn match_explanations() {
+    let parser = Parser::new("+jordan,+hoo*ie,+sneaker~,-youth", Prefixes::default());
+    let matcher = parser.parse().compile();
+
+    let explanation = matcher.explain("Jordan Hoodie");
+    assert!(explanation.matched);
+    let hits: Vec<_> = explanation
+        .positive
+        .iter()
+        .map(|hit| (hit.keyword.text.as_str(), hit.range.clone()))
+        .collect();
+    assert_eq!(hits, vec![("jordan", Some(0..6)), ("hoo*ie", Some(7..13))]);
+    assert!(explanation.negative.is_empty());
+
+    // ranges point into the original title even when lowercasing changes its length
+    let title = "İSTANBUL Jordan Snekaer (Youth)";
+    let explanation = matcher.explain(title);
+    assert!(!explanation.matched);
+    let jordan = explanation.positive[0].range.clone().unwrap();
+    assert_eq!(&title[jordan], "Jordan");
+    assert_eq!(explanation.positive[1].keyword, "sneaker");
+    assert_eq!(explanation.positive[1].range, None);
+    assert_eq!(explanation.positive[1].distance, 1);
+    let youth = explanation.negative[0].range.clone().unwrap();
+    assert_eq!(&title[youth], "Youth");
+
+    let explanation = matcher.explain("Air Max 90");
+    assert!(!explanation.matched);
+    assert!(explanation.positive.is_empty());
+}
+
+struct Product {
+    title: &'static str,
+    vendor: &'static str,
+    tags: Vec<&'static str>,
+    product_type: &'static str,
+    sku: &'static str,
+}
+
+impl Matchable for Product {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "title" => Some(Cow::Borrowed(self.title)),
+            "vendor" => Some(Cow::Borrowed(self.vendor)),
+            "tags" => Some(Cow::Owned(self.tags.join(", "))),
+            "product_type" => Some(Cow::Borrowed(self.product_type)),
+            "sku" => Some(Cow::Borrowed(self.sku)),
+            _ => None,
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn structured_products() {
+    let products = vec![
+        Product {
+            title: "Dunk Low Panda",
+            vendor: "Nike",
+            tags: vec!["sneakers", "restock"],
+            product_type: "Footwear",
+            sku: "DD1391-100",
+        },
+        Product {
+            title: "Club Hoodie",
+            vendor: "Nike",
+            tags: vec!["apparel"],
+            product_type: "Tops",
+            sku: "BV2654-010",
+        },
+        Product {
+            title: "Samba OG",
+            vendor: "Adidas",
+            tags: vec!["sneakers"],
+            product_type: "Footwear",
+            sku: "B75806",
+        },
+    ];
+
+    let mut matcher = Parser::new("+nike,+dd1391,-apparel", Prefixes::default())
+        .parse()
+        .compile();
+    // only titles are searched by default
+    assert!(matcher.match_indices(&products).is_empty());
+
+    matcher.set_fields(&["title", "vendor", "tags", "sku"]);
+    assert_eq!(matcher.match_indices(&products), vec![0]);
+    let matched = matcher.match_items(&products);
+    assert_eq!(matched.len(), 1);
+    assert!(std::ptr::eq(matched[0], &products[0]));
+
+    // keywords never span two fields
+    let mut matcher = Parser::new("+panda nike", Prefixes::default())
+        .parse()
+        .compile();
+    matcher.set_fields(&["title", "vendor"]);
+    assert!(!matcher.is_match(&products[0]));
+
+    let mut matcher = Parser::new("+footwear,+sneakrs~", Prefixes::default())
+        .parse()
+        .compile();
+    matcher.set_fields(&["title", "product_type", "tags"]);
+    let explanation = matcher.explain(&products[2]);
+    assert!(explanation.matched);
+    assert_eq!(explanation.positive[0].field, "product_type");
+    assert_eq!(explanation.positive[0].range, Some(0..8));
+    assert_eq!(explanation.positive[1].field, "tags");
+    assert_eq!(explanation.positive[1].distance, 1);
+}
+
+#[wasm_bindgen_test]
+fn field_scoped_keywords() {
+    const FIELDS: [&str; 4] = ["title", "vendor", "tags", "sku"];
+    let with_fields = |input: &'static str| {
+        let mut parser = Parser::new(input, Prefixes::default());
+        parser.set_fields(Some(&FIELDS));
+        parser
+    };
+
+    let parser = with_fields(
+        r#"+vendor:nike,+=title:dunk,+sku:=dd1391~,+"title:foo",+title\:bar,-tags:restock"#,
+    );
+    let keywords = parser.try_parse().unwrap();
+    let scoped: Vec<_> = keywords
+        .positive
+        .iter()
+        .chain(&keywords.negative)
+        .map(|k| (k.field.as_deref(), k.text.as_str()))
+        .collect();
+    assert_eq!(
+        scoped,
+        vec![
+            (Some("vendor"), "nike"),
+            (Some("title"), "dunk"),
+            (Some("sku"), "dd1391"),
+            (None, "title:foo"),
+            (None, "title:bar"),
+            (Some("tags"), "restock"),
+        ]
+    );
+    assert_eq!(keywords.positive[1].mode, MatchMode::Word);
+    assert_eq!(keywords.positive[2].mode, MatchMode::Word);
+    assert_eq!(keywords.positive[2].fuzziness, Fuzziness::AUTO);
+
+    let errors = with_fields("+sku:/dd(/").try_parse().unwrap_err();
+    assert_eq!(errors[0].span, 5..10);
+
+    let products = vec![
+        Product {
+            title: "Dunk Low Panda",
+            vendor: "Nike",
+            tags: vec!["sneakers", "restock"],
+            product_type: "Footwear",
+            sku: "DD1391-100",
+        },
+        Product {
+            title: "Restock Dunk High",
+            vendor: "Nike",
+            tags: vec!["sneakers"],
+            product_type: "Footwear",
+            sku: "DD1399-100",
+        },
+        Product {
+            title: "Nike Samba",
+            vendor: "Adidas",
+            tags: vec!["sneakers"],
+            product_type: "Footwear",
+            sku: "B75806",
+        },
+    ];
+
+    // scoped keywords only look at their own field, whatever the defaults are
+    let matcher = with_fields("+vendor:nike,-tags:restock").parse().compile();
+    assert_eq!(matcher.match_indices(&products), vec![1]);
+    assert_eq!(matcher.fields(), &["title"]);
+
+    // unscoped keywords look at the default fields
+    let mut matcher = Parser::new("+nike,-restock", Prefixes::default())
+        .parse()
+        .compile();
+    assert_eq!(matcher.match_indices(&products), vec![2]);
+    matcher.set_fields(&["vendor", "tags"]);
+    assert_eq!(matcher.match_indices(&products), vec![1]);
+
+    let matcher = with_fields("+title:dunk,+sku:dd1391").parse().compile();
+    let explanation = matcher.explain(&products[0]);
+    let hits: Vec<_> = explanation
+        .positive
+        .iter()
+        .map(|hit| (hit.field, hit.range.clone()))
+        .collect();
+    assert_eq!(hits, vec![("title", Some(0..4)), ("sku", Some(0..6))]);
+
+    // plain strings only have a title
+    let matcher = with_fields("+title:dunk,+vendor:nike").parse().compile();
+    assert_eq!(
+        matcher.match_products(&["Dunk Low", "Nike Air Max"]),
+        vec!["Dunk Low"]
+    );
+
+    // names that aren't known fields stay part of the keyword
+    let parser = Parser::new("+ref:abc,+http://x.com", Prefixes::default());
+    let keywords = parser.try_parse().unwrap();
+    assert_eq!(keywords.positive, vec!["ref:abc", "http://x.com"]);
+    assert!(keywords.positive.iter().all(|k| k.field.is_none()));
+    assert_eq!(keywords.positive[1].pattern, None);
+    let matcher = keywords.compile();
+    assert_eq!(
+        matcher.match_products(&["Tee REF:ABC", "See http://x.com", "Dunk Low"]),
+        vec!["Tee REF:ABC", "See http://x.com"]
+    );
+
+    let mut parser = Parser::new("+ref:abc", Prefixes::default());
+    parser.set_fields(None);
+    assert_eq!(parser.parse().positive[0].field.as_deref(), Some("ref"));
+}
+
+struct Listing {
+    title: &'static str,
+    price: &'static str,
+    stock: u32,
+}
+
+impl Matchable for Listing {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "title" => Some(Cow::Borrowed(self.title)),
+            "price" => Some(Cow::Borrowed(self.price)),
+            _ => None,
+        }
+    }
+
+    fn number(&self, name: &str) -> Option<f64> {
+        match name {
+            "stock" => Some(self.stock.into()),
+            _ => self.field(name)?.trim().parse().ok(),
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn numeric_predicates() {
+    let parser = Parser::new(
+        "+dunk,price<200,price>=90,stock>0,-youth",
+        Prefixes::default(),
+    );
+    let keywords = parser.try_parse().unwrap();
+    let predicates: Vec<_> = keywords
+        .predicates
+        .iter()
+        .map(|p| (p.field.as_str(), p.comparison, p.value))
+        .collect();
+    assert_eq!(
+        predicates,
+        vec![
+            ("price", Comparison::Less, 200.0),
+            ("price", Comparison::GreaterOrEqual, 90.0),
+            ("stock", Comparison::Greater, 0.0),
+        ]
+    );
+    assert!(keywords.other.is_empty());
+
+    let listings = vec![
+        Listing {
+            title: "Dunk Low",
+            price: "110.00",
+            stock: 4,
+        },
+        Listing {
+            title: "Dunk Low (Youth)",
+            price: "85.00",
+            stock: 2,
+        },
+        Listing {
+            title: "Dunk High",
+            price: "125.00",
+            stock: 0,
+        },
+        Listing {
+            title: "Dunk Low Pro SB",
+            price: "250.00",
+            stock: 1,
+        },
+        Listing {
+            title: "Dunk Low Retro",
+            price: "89.99",
+            stock: 6,
+        },
+        Listing {
+            title: "Dunk Low Sample",
+            price: "TBA",
+            stock: 1,
+        },
+    ];
+    let matcher = keywords.compile();
+    assert_eq!(matcher.match_indices(&listings), vec![0]);
+
+    let explanation = matcher.explain(&listings[2]);
+    assert!(!explanation.matched);
+    assert_eq!(explanation.failed.len(), 1);
+    assert_eq!(explanation.failed[0].field, "stock");
+
+    // strings have no numeric fields
+    assert!(!matcher.is_match("Dunk Low"));
+
+    // without positive keywords, predicates select every product that satisfies them
+    let matcher = Parser::new("price<100,-youth", Prefixes::default())
+        .try_parse()
+        .unwrap()
+        .compile();
+    assert_eq!(matcher.match_indices(&listings), vec![4]);
+    assert_eq!(matcher.score(&listings[4]), Some(0.0));
+    assert!(matcher.explain(&listings[4]).matched);
+    assert!(!matcher.explain(&listings[1]).matched);
+
+    let errors = Parser::new("+dunk,price<cheap,stock>", Prefixes::default())
+        .try_parse()
+        .unwrap_err();
+    let found: Vec<_> = errors.iter().map(|e| (e.code, e.span.clone())).collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorCode::InvalidPredicate, 12..17),
+            (ErrorCode::InvalidPredicate, 23..24),
+        ]
+    );
+    // lenient parsing keeps malformed predicates in `other`
+    let keywords = Parser::new("+dunk,price<cheap", Prefixes::default()).parse();
+    assert!(keywords.predicates.is_empty());
+    assert_eq!(keywords.other, vec!["price<cheap"]);
+}
+
+struct Shoe {
+    title: &'static str,
+    size: &'static str,
+}
+
+impl Matchable for Shoe {
+    fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "title" => Some(Cow::Borrowed(self.title)),
+            "size" => Some(Cow::Borrowed(self.size)),
+            _ => None,
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn size_predicates() {
+    let found: Vec<_> = [
+        "9.5",
+        "US W 11",
+        "27.5 cm",
+        "EU 42.5 / UK 8",
+        "Men's 10",
+        "OS",
+    ]
+    .iter()
+    .map(|size| Size::find(size).map(|s| (s.system, s.value)))
+    .collect();
+    assert_eq!(
+        found,
+        vec![
+            Some((SizeSystem::UsMen, 9.5)),
+            Some((SizeSystem::UsWomen, 11.0)),
+            Some((SizeSystem::Cm, 27.5)),
+            Some((SizeSystem::Eu, 42.5)),
+            Some((SizeSystem::UsMen, 10.0)),
+            None,
+        ]
+    );
+    let women = Size {
+        system: SizeSystem::UsWomen,
+        value: 10.5,
+    };
+    assert_eq!(women.convert(SizeSystem::UsMen).unwrap().value, 9.0);
+    let cm = Size {
+        system: SizeSystem::Cm,
+        value: 27.0,
+    };
+    assert_eq!(cm.convert(SizeSystem::Uk).unwrap().value, 8.0);
+
+    // the chart's ends convert, sizes beyond them don't
+    let convert = |system, value, to| Size { system, value }.convert(to).map(|s| s.value);
+    assert_eq!(convert(SizeSystem::Cm, 22.5, SizeSystem::UsMen), Some(3.5));
+    assert_eq!(convert(SizeSystem::Uk, 15.0, SizeSystem::UsMen), Some(16.0));
+    assert_eq!(convert(SizeSystem::Cm, 0.1, SizeSystem::UsMen), None);
+    assert_eq!(convert(SizeSystem::Eu, 60.0, SizeSystem::Cm), None);
+    assert_eq!(convert(SizeSystem::UsMen, 2.0, SizeSystem::Uk), None);
+    assert_eq!(convert(SizeSystem::UsMen, 18.0, SizeSystem::Eu), None);
+    assert_eq!(
+        convert(SizeSystem::UsMen, 18.0, SizeSystem::UsWomen),
+        Some(19.5)
+    );
+    let sizes = Parser::new("size:cm10,uk20", Prefixes::default())
+        .try_parse()
+        .unwrap()
+        .sizes;
+    assert!(sizes[0].matches_size("10 cm"));
+    assert!(sizes[0].matches_size("UK 20"));
+    assert!(!sizes[0].matches_size("US 3.5"));
+    assert!(!sizes[0].matches_size("US 16"));
+
+    let parser = Parser::new(
+        "+dunk,size:9-11.5,size:uk8,eu44,-youth",
+        Prefixes::default(),
+    );
+    let keywords = parser.try_parse().unwrap();
+    assert_eq!(keywords.sizes.len(), 2);
+    let ranges: Vec<_> = keywords.sizes[1]
+        .ranges
+        .iter()
+        .map(|r| (r.system, r.low, r.high))
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![(SizeSystem::Uk, 8.0, 8.0), (SizeSystem::Eu, 44.0, 44.0)]
+    );
+    assert!(keywords.other.is_empty());
+
+    let range = &keywords.sizes[0];
+    assert!(range.matches_size("US 9"));
+    assert!(range.matches_size("EU 44"));
+    assert!(range.matches_size("W 12"));
+    assert!(!range.matches_size("UK 11"));
+    assert!(!range.matches_size("8.5"));
+
+    let shoes = vec![
+        Shoe {
+            title: "Dunk Low",
+            size: "US 9",
+        },
+        Shoe {
+            title: "Dunk Low",
+            size: "US 9.5",
+        },
+        Shoe {
+            title: "Dunk Low",
+            size: "EU 44",
+        },
+        Shoe {
+            title: "Dunk Low (Youth)",
+            size: "UK 8",
+        },
+        Shoe {
+            title: "Dunk Low",
+            size: "One Size",
+        },
+    ];
+    let matcher = keywords.compile();
+    assert_eq!(matcher.match_indices(&shoes), vec![0, 2]);
+    assert_eq!(matcher.explain(&shoes[1]).failed_sizes.len(), 1);
+
+    let matcher = Parser::new("size:uk8,eu44", Prefixes::default())
+        .try_parse()
+        .unwrap()
+        .compile();
+    assert_eq!(matcher.match_indices(&shoes), vec![0, 2, 3]);
+
+    let errors = Parser::new("+dunk,size:huge,eu42", Prefixes::default())
+        .try_parse()
+        .unwrap_err();
+    let found: Vec<_> = errors.iter().map(|e| (e.code, e.span.clone())).collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorCode::InvalidPredicate, 11..15),
+            (ErrorCode::MissingPrefix, 16..20),
+        ]
+    );
+
+    // words with non-ASCII letters are not sizes
+    assert_eq!(
+        Size::find("Größe 42").map(|s| (s.system, s.value)),
+        Some((SizeSystem::UsMen, 42.0))
+    );
+    assert_eq!(Size::find("US 9½"), None);
+    let parser = Parser::new("+dunk,size:9é", Prefixes::default());
+    assert_eq!(parser.parse().sizes.len(), 0);
+    let errors = parser.try_parse().unwrap_err();
+    assert_eq!(errors[0].code, ErrorCode::InvalidPredicate);
+}
+
+#[wasm_bindgen_test]
+fn round_trip_formatting() {
+    let inputs = [
+        r#"+foo,-bar,+"air max 1, 86",+\"quoted\",+back\\slash"#,
+        r#"+=dunk~,+air*1^2,+sb~1^0.5,+"size?"*,+vendor:=nike,-tags:restock"#,
+        r#"+\=not word,+"/not regex/",+"title:plain",+" padded ",+c++"#,
+        r#"\-baz,"price<200",foo,price<200,stock>=1,size:9-11.5,size:uk8,eu42,w10"#,
+    ];
+    for input in inputs {
+        let keywords = Parser::new(input, Prefixes::default()).parse();
+        let formatted = keywords.to_string();
+        let reparsed = Parser::new(&formatted, Prefixes::default()).parse();
+        assert_eq!(reparsed, keywords, "{} => {}", input, formatted);
+    }
+
+    let keywords = Parser::new(
+        r#"+"air max 1, 86",+dunk,-youth,\-baz"#,
+        Prefixes::default(),
+    )
+    .parse();
+    assert_eq!(
+        keywords.to_string(),
+        r#"+"air max 1, 86",+dunk,-youth,"-baz""#
+    );
+
+    let empty = Keywords::default().to_string();
+    assert_eq!(empty, "");
+    assert_eq!(
+        Parser::new(&empty, Prefixes::default()).parse(),
+        Keywords::default()
+    );
+
+    let prefixes = Prefixes {
+        positive: "pos:",
+        negative: "neg:",
+    };
+    let keywords = Parser::new("pos:foo,neg:bar,neg:baz", prefixes).parse();
+    let reparsed = Parser::new(&keywords.format(prefixes), prefixes).parse();
+    assert_eq!(reparsed, keywords);
+
+    let tokens: Vec<String> = Parser::new(r#"+foo, -"bar""#, Prefixes::default())
+        .tokens()
+        .map(|token| token.to_string())
+        .collect();
+    assert_eq!(tokens, vec!["+foo", r#" -"bar""#]);
+
+    let canonical = Parser::new(
+        "+Jordan,+AIR*1,-Youth,-youth,size:uk8,price<200,+jordan,Foo",
+        Prefixes::default(),
+    )
+    .parse()
+    .canonical();
+    assert_eq!(
+        canonical.to_string(),
+        "+air*1,+jordan,-youth,foo,price<200,size:uk8"
+    );
+    assert_eq!(canonical.canonical(), canonical);
+}
+
+#[wasm_bindgen_test]
+fn borrowed_parsing() {
+    let input = String::from(r#"+vendor:nike~1,-"a,b",+c\+\+,+=max^2,price<200,size:9-10,bar"#);
+    let mut parser = Parser::new(&input, Prefixes::default());
+    parser.set_fields(Some(&["vendor"]));
+    let keywords = parser.parse_borrowed();
+
+    assert!(matches!(keywords.positive[0].text, Cow::Borrowed("nike")));
+    assert_eq!(keywords.positive[0].field, Some("vendor"));
+    assert_eq!(keywords.positive[0].fuzziness, Fuzziness::Fixed(1));
+    assert!(matches!(keywords.positive[1].text, Cow::Owned(_)));
+    assert_eq!(keywords.positive[1], "c++");
+    assert!(matches!(keywords.positive[2].text, Cow::Borrowed("max")));
+    assert_eq!(keywords.positive[2].mode, MatchMode::Word);
+    assert_eq!(keywords.positive[2].weight, 2.0);
+    assert_eq!(keywords.negative[0], "a,b");
+    assert_eq!(keywords.predicates.len(), 1);
+    assert_eq!(keywords.sizes.len(), 1);
+    assert!(matches!(keywords.other[0], Cow::Borrowed("bar")));
+
+    let matcher = keywords.compile();
+    let owned = keywords.into_owned();
+    assert_eq!(owned, parser.parse());
+    assert_eq!(
+        owned.compile().explain("c++ max").matched,
+        matcher.explain("c++ max").matched
+    );
+
+    let mut parser = Parser::new("+foo", Prefixes::default());
+    parser.should_retain_prefix(true);
+    assert_eq!(parser.parse_borrowed().positive, vec!["+foo"]);
+}
+
+#[wasm_bindgen_test]
+fn lazy_filtering() {
+    let matcher = Parser::new("+jordan,-youth", Prefixes::default())
+        .parse()
+        .compile();
+    let products = vec![
+        "Jordan 1 Youth",
+        "Jordan 1 High",
+        "Dunk Low",
+        "Jordan 4 Retro",
+    ];
+
+    let filtered: Vec<&str> = matcher.filter(products.iter().copied()).collect();
+    assert_eq!(filtered, matcher.match_products(&products));
+    let reversed: Vec<&&str> = products.iter().filter_keywords(&matcher).rev().collect();
+    assert_eq!(reversed, vec![&"Jordan 4 Retro", &"Jordan 1 High"]);
+
+    // products past the first match are never pulled from the source
+    let mut checked = 0;
+    let first = products
+        .iter()
+        .inspect(|_| checked += 1)
+        .filter_keywords(&matcher)
+        .next();
+    assert_eq!(first, Some(&"Jordan 1 High"));
+    assert_eq!(checked, 2);
+
+    let owned = vec![String::from("Jordan 11"), String::from("Youth Tee")];
+    let filtered: Vec<String> = matcher.filter(owned).collect();
+    assert_eq!(filtered, vec!["Jordan 11"]);
+}
+
+#[wasm_bindgen_test]
+fn variant_matching() {
+    let products = vec![
+        WithVariants {
+            product: "New Balance 990v6",
+            variants: vec!["Grey / 9 / D", "Grey / 9 / 2E Wide", "Navy / 10 / D"],
+        },
+        WithVariants {
+            product: "New Balance 990v6 Wide",
+            variants: vec!["Grey / 11 / 4E"],
+        },
+        WithVariants {
+            product: "New Balance Gift Card",
+            variants: vec![],
+        },
+    ];
+    let matcher = Parser::new("+990,-wide", Prefixes::default())
+        .parse()
+        .compile();
+
+    // a negative keyword that only hits a variant rejects that variant alone
+    assert_eq!(
+        matcher.match_variants(&products[0]),
+        vec![&"Grey / 9 / D", &"Navy / 10 / D"]
+    );
+    // while one that hits the product rejects every variant
+    assert!(matcher.match_variants(&products[1]).is_empty());
+
+    let matches = matcher.match_listings(&products);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].product, &products[0]);
+    assert_eq!(matches[0].variants.len(), 2);
+
+    // a plain keyword never spans the product and the variant text
+    let matcher = Parser::new("+990v6,+navy,-\"990v6 navy\"", Prefixes::default())
+        .parse()
+        .compile();
+    assert_eq!(
+        matcher.match_variants(&products[0]),
+        vec![&"Grey / 9 / D", &"Grey / 9 / 2E Wide", &"Navy / 10 / D"]
+    );
+    // while a glob may
+    let matcher = Parser::new("+990v6*wide", Prefixes::default())
+        .parse()
+        .compile();
+    assert_eq!(
+        matcher.match_variants(&products[0]),
+        vec![&"Grey / 9 / 2E Wide"]
+    );
+
+    // string variants are sized by their text
+    let matcher = Parser::new("+990,size:9", Prefixes::default())
+        .try_parse()
+        .unwrap()
+        .compile();
+    assert_eq!(
+        matcher.match_variants(&products[0]),
+        vec![&"Grey / 9 / D", &"Grey / 9 / 2E Wide"]
+    );
+    assert!(matcher.match_variants(&products[1]).is_empty());
+
+    // products without variants match on their own
+    let matcher = Parser::new("+gift card", Prefixes::default())
+        .parse()
+        .compile();
+    let matches = matcher.match_listings(&products);
+    assert_eq!(matches.len(), 1);
+    assert!(matches[0].variants.is_empty());
+
+    // variant numbers come first, and fields of either side can be scoped to
+    let listing = WithVariants {
+        product: Listing {
+            title: "Dunk Low",
+            price: "110",
+            stock: 3,
+        },
+        variants: vec![
+            Listing {
+                title: "Panda",
+                price: "120",
+                stock: 0,
+            },
+            Listing {
+                title: "Grey Fog",
+                price: "100",
+                stock: 2,
+            },
+        ],
+    };
+    let matcher = Parser::new("+dunk,price<=110,stock>0", Prefixes::default())
+        .parse()
+        .compile();
+    let variants = matcher.match_variants(&listing);
+    assert_eq!(variants.len(), 1);
+    assert_eq!(variants[0].title, "Grey Fog");
+    assert!(matcher.is_match(&listing.product));
+}